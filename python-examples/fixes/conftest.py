@@ -0,0 +1,44 @@
+from sqlite3 import Connection, connect
+from typing import Dict, Iterator, Optional
+
+import numpy as np
+import pytest
+
+from app.models import User
+from models import Account
+
+
+class Session:
+    pass
+
+
+@pytest.fixture()
+def user() -> User:
+    return User()
+
+
+@pytest.fixture()
+def account() -> Account:
+    return Account()
+
+
+@pytest.fixture()
+def settings() -> Optional[Dict[str, int]]:
+    return None
+
+
+@pytest.fixture()
+def matrix() -> np.ndarray:
+    return np.zeros(2)
+
+
+@pytest.fixture()
+def connection() -> Iterator[Connection]:
+    db = connect(":memory:")
+    yield db
+    db.close()
+
+
+@pytest.fixture()
+def session() -> Session:
+    return Session()
@@ -0,0 +1,21 @@
+"""Tests with missing argument annotations."""
+import pytest
+from typing import Optional
+
+from models import Account
+
+
+def test_user(user, account):
+    assert user
+
+
+def test_settings(settings, matrix):
+    assert settings
+
+
+def test_connection(connection, session):
+    assert connection
+
+
+def test_wrong_types(user: int, connection: str):
+    assert user
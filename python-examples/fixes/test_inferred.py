@@ -0,0 +1,40 @@
+import pytest
+
+from app.models import User
+
+
+@pytest.fixture()
+def name():
+    return "alice"
+
+
+@pytest.fixture(params=[1, 2])
+def number(request):
+    return request.param
+
+
+@pytest.fixture()
+def admin(user):
+    return user
+
+
+@pytest.fixture()
+def created_user():
+    user = User()
+    yield user
+
+
+@pytest.fixture()
+def scores(
+    name: str,
+):
+    return {name: [1.5]}
+
+
+@pytest.fixture()
+def unknown():
+    return compute()
+
+
+def test_users(name: str, number: int, admin: User, created_user: User, unknown: int):
+    assert scores
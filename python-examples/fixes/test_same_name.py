@@ -0,0 +1,11 @@
+"""Test classes with methods sharing their name."""
+
+
+class TestFirst:
+    def test_user(self, user: int):
+        assert user
+
+
+class TestSecond:
+    def test_user(self, user):
+        assert user
@@ -0,0 +1,20 @@
+import pytest
+
+
+class Settings:
+    pass
+
+
+@pytest.fixture(scope="session")
+def settings() -> Settings:
+    return Settings()
+
+
+@pytest.fixture(scope="session")
+def database(settings: Settings) -> dict[str, str]:
+    return {}
+
+
+@pytest.fixture(autouse=True)
+def cleanup() -> None:
+    pass
@@ -0,0 +1,20 @@
+import pytest
+
+
+@pytest.fixture()
+def database(database: dict[str, str]) -> dict[str, str]:
+    return database
+
+
+@pytest.fixture()
+def user(database: dict[str, str]) -> str:
+    return database["user"]
+
+
+def test_settings(request: pytest.FixtureRequest):
+    request.getfixturevalue("settings")
+
+
+class TestUsers:
+    def test_user(self, user: str):
+        pass
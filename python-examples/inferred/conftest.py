@@ -0,0 +1,6 @@
+import pytest
+
+
+@pytest.fixture()
+def port():
+    return 8080
@@ -0,0 +1,63 @@
+from typing import Dict, List, Optional
+
+import pytest
+
+
+@pytest.fixture()
+def numbers():
+    return [1, 2, 3]
+
+
+@pytest.fixture()
+def greeting():
+    yield "hello"
+
+
+@pytest.fixture()
+def unknown(request: pytest.FixtureRequest):
+    return request.getfixturevalue("port")
+
+
+@pytest.fixture()
+def empty_list():
+    return []
+
+
+@pytest.fixture()
+def empty_dict():
+    return {}
+
+
+@pytest.fixture(params=[1, None])
+def maybe_number(request: pytest.FixtureRequest):
+    return request.param
+
+
+@pytest.fixture()
+def ratio():
+    return 1
+
+
+def test_matching(numbers: List[int], greeting: str, port: int):
+    pass
+
+
+def test_mismatching(numbers: list[str], greeting: bytes, port: str):
+    pass
+
+
+def test_not_inferred(unknown: str):
+    pass
+
+
+def test_compatible(
+    empty_list: List[int],
+    empty_dict: Dict[str, int],
+    maybe_number: Optional[int],
+    ratio: float,
+):
+    pass
+
+
+def test_incompatible(maybe_number: int, ratio: str):
+    pass
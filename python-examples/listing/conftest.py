@@ -0,0 +1,13 @@
+from pathlib import Path
+
+import pytest
+
+
+@pytest.fixture(scope="session", autouse=True)
+def settings() -> Path:
+    return Path("settings.toml")
+
+
+@pytest.fixture()
+def database() -> dict[str, str]:
+    return {}
@@ -0,0 +1,6 @@
+import pytest
+
+
+@pytest.fixture(scope="module")
+def database():
+    return {"users": 1}
@@ -0,0 +1,10 @@
+import pytest
+
+
+@pytest.fixture()
+def client(database: dict[str, int]) -> str:
+    return "client"
+
+
+def test_client(client: str, settings):
+    pass
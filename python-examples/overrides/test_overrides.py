@@ -0,0 +1,25 @@
+import pytest
+
+
+@pytest.fixture()
+def database(database: str) -> str:
+    return database + '/test'
+
+
+@pytest.fixture()
+def user_id() -> str:
+    return '1'
+
+
+@pytest.fixture()
+def token() -> str:
+    return 'a'
+
+
+@pytest.fixture()
+def token() -> str:
+    return 'b'
+
+
+def test_user(user_id: str, token: str, database: str):
+    pass
@@ -0,0 +1 @@
+pytest_plugins = ['plugins.shared.db']
@@ -0,0 +1,11 @@
+import pytest
+
+
+@pytest.fixture()
+def database() -> str:
+    return 'sqlite://'
+
+
+@pytest.fixture()
+def connection() -> int:
+    return 1
@@ -0,0 +1,5 @@
+from .engine import Engine
+
+
+class Account:
+    engine = Engine
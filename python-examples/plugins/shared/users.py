@@ -0,0 +1,13 @@
+from .models import Account
+
+import pytest
+
+
+@pytest.fixture()
+def user_name() -> str:
+    return 'user'
+
+
+@pytest.fixture()
+def user_id() -> int:
+    return 1
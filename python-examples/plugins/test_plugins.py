@@ -0,0 +1,7 @@
+from .shared.users import user_name
+from plugins.shared.users import user_id as account_id
+from plugins.shared.models import Account
+
+
+def test_plugins(database: str, connection: str, user_name: str, account_id: int, missing: int):
+    pass
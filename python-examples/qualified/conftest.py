@@ -0,0 +1,14 @@
+from configparser import ConfigParser as Config
+import pathlib
+
+import pytest
+
+
+@pytest.fixture()
+def config() -> Config:
+    return Config()
+
+
+@pytest.fixture()
+def data_dir() -> pathlib.Path:
+    return pathlib.Path('.')
@@ -0,0 +1,22 @@
+import typing
+import typing as t
+from pathlib import Path
+
+import pytest
+
+
+class Config:
+    pass
+
+
+@pytest.fixture()
+def numbers() -> t.List[int]:
+    return [1]
+
+
+def test_paths(data_dir: Path, numbers: typing.List[int]):
+    pass
+
+
+def test_config(config: Config):
+    pass
@@ -0,0 +1,16 @@
+import pytest
+
+
+@pytest.fixture()
+def user() -> str:
+    return 'user'
+
+
+class TestFirst:
+    def test_create(self, user: int):  # pytest-typechecker: ignore
+        pass
+
+
+class TestSecond:
+    def test_create(self, user: int):
+        pass
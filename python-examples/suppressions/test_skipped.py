@@ -0,0 +1,6 @@
+# pytest-typechecker: skip-file
+import pytest
+
+
+def test_skipped(missing_fixture):
+    pass
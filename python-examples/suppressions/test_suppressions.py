@@ -0,0 +1,30 @@
+import pytest
+
+
+@pytest.fixture()
+def user() -> str:
+    return 'user'
+
+
+@pytest.fixture()
+def unused_legacy() -> str:  # pytest-typechecker: ignore[PTC011]
+    return 'legacy'
+
+
+def test_untyped(user):  # pytest-typechecker: ignore[PTC003]
+    pass
+
+
+def test_bare_ignore(  # pytest-typechecker: ignore
+    user: int,  # pytest-typechecker: ignore
+    missing: str,
+):
+    pass
+
+
+def test_wrong_code(user: int):  # pytest-typechecker: ignore[PTC003]
+    pass
+
+
+def test_nothing_to_ignore(user: str):  # pytest-typechecker: ignore
+    pass
@@ -0,0 +1,44 @@
+from typing import Annotated, Callable, Dict, Literal, Optional, Tuple
+
+import pytest
+
+
+def make_type():
+    return int
+
+
+@pytest.fixture()
+def mode() -> Literal["r", "w"]:
+    return "r"
+
+
+@pytest.fixture()
+def callback() -> Callable[[int], str]:
+    return str
+
+
+@pytest.fixture()
+def sizes() -> Tuple[int, ...]:
+    return (1, 2)
+
+
+@pytest.fixture()
+def positive() -> Annotated[int, "positive"]:
+    return 1
+
+
+@pytest.fixture()
+def factory() -> make_type():
+    return 1
+
+
+def test_types(callback: Callable[[int], str], sizes: Tuple[int, ...], positive: int, lookup: Optional[Dict[str, None]] = None):
+    pass
+
+
+def test_factory(factory: "List["):
+    pass
+
+
+def test_mode(mode: Literal["r"]):
+    pass
@@ -0,0 +1,42 @@
+import functools
+from unittest import mock
+
+import pytest
+
+
+@pytest.fixture()
+def user_name() -> str:
+    return 'user'
+
+
+def test_defaults(user_name: str, retries: int = 3, *args, **kwargs):
+    pass
+
+
+def test_keyword_only(*, user_name: int, verbose: bool = False):
+    pass
+
+
+def test_positional_only(user_name, /):
+    pass
+
+
+@mock.patch('os.getcwd')
+def test_patched(getcwd, user_name: str):
+    pass
+
+
+class TestMethods:
+    def test_method(self, user_name: int):
+        pass
+
+    @staticmethod
+    def test_static(user_name: str):
+        pass
+
+
+def _check_user(prefix, user_name: int):
+    pass
+
+
+test_partial = functools.partial(_check_user, 'prefix')
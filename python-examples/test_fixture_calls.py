@@ -0,0 +1,20 @@
+import pytest
+
+
+@pytest.fixture()
+def make_user() -> str:
+    return 'user'
+
+
+@pytest.fixture()
+def admin() -> str:
+    return make_user() + ' admin'
+
+
+def test_direct_call():
+    make_user_name = make_user()
+    assert make_user_name == make_user()
+
+
+def test_requested(make_user: str):
+    assert callable(make_user) or make_user()
@@ -0,0 +1,27 @@
+from __future__ import annotations
+
+from typing import List
+
+import pytest
+
+
+class User:
+    pass
+
+
+@pytest.fixture()
+def user() -> "User":
+    return User()
+
+
+@pytest.fixture()
+def users(user: User) -> List["User"]:
+    return [user]
+
+
+def test_users(user: User, users: "List[User]"):
+    pass
+
+
+def test_user_id(user: "int"):
+    pass
@@ -0,0 +1,35 @@
+from typing import Generator, Iterator
+
+import pytest
+
+
+class User:
+    pass
+
+
+@pytest.fixture()
+def user() -> Iterator[User]:
+    yield User()
+
+
+@pytest.fixture()
+def session() -> Generator[str, None, None]:
+    yield "session"
+
+
+@pytest.fixture()
+def admin() -> "Iterator[User]":
+    yield User()
+
+
+@pytest.fixture()
+def users() -> Iterator[User]:
+    return iter([User()])
+
+
+def test_values(user: User, session: str, admin: User, users: Iterator[User]):
+    pass
+
+
+def test_generator_types(user: Iterator[User], session: int):
+    pass
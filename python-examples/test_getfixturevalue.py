@@ -0,0 +1,14 @@
+from pytest import FixtureRequest
+import pytest
+
+
+@pytest.fixture()
+def user_name() -> str:
+    return 'user'
+
+
+def test_lookup(request: FixtureRequest):
+    name: str = request.getfixturevalue('user_name')
+    count: int = request.getfixturevalue('user_name')
+    missing = request.getfixturevalue('user_nme')
+    assert name and count and missing
@@ -0,0 +1,47 @@
+import datetime
+import pathlib
+from pathlib import Path
+from typing import Any, List, Optional, Sequence
+
+import pytest
+from hypothesis import given
+from hypothesis import strategies as st
+
+
+class User:
+    pass
+
+
+@pytest.fixture()
+def user_name() -> str:
+    return 'user'
+
+
+@given(value=st.integers(), names=st.lists(st.text()))
+def test_keywords(user_name: str, value: int, names: List[str]):
+    pass
+
+
+@given(st.integers(), st.booleans())
+def test_positional(user_name: str, count: int, flag: str):
+    pass
+
+
+@given(user=st.builds(User), ratio=st.floats().filter(lambda x: x > 0))
+def test_builds(user: User, ratio: int):
+    pass
+
+
+@given(data=st.sampled_from([1, 2]))
+def test_unknown_strategy(data: str):
+    pass
+
+
+@given(path=st.builds(Path), day=st.dates())
+def test_qualified_types(path: pathlib.Path, day: datetime.date):
+    pass
+
+
+@given(a=st.integers(), b=st.integers(), c=st.integers(), xs=st.lists(st.integers()))
+def test_widened_types(a: Any, b: Optional[int], c: float, xs: Sequence[int]):
+    pass
@@ -0,0 +1,36 @@
+from unittest import mock
+from unittest.mock import MagicMock, patch
+
+import pytest
+
+
+@pytest.fixture()
+def user_name() -> str:
+    return 'user'
+
+
+@patch.object(mock, 'DEFAULT')
+@patch('os.getcwd')
+def test_patch_object(getcwd: MagicMock, default: mock.Mock, user_name: str):
+    pass
+
+
+@patch.dict('os.environ', {'A': '1'})
+@patch('os.getcwd', new=lambda: '/')
+def test_patch_dict(user_name: str):
+    pass
+
+
+@patch.multiple('os', getcwd=mock.DEFAULT, sep='/')
+def test_patch_multiple(user_name: str, getcwd: MagicMock):
+    pass
+
+
+@patch('os.getcwd')
+def test_unannotated_mock(getcwd, user_name: str):
+    pass
+
+
+@patch('os.getcwd')
+def test_wrong_mock_type(getcwd: str, user_name: str):
+    pass
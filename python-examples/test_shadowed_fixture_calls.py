@@ -0,0 +1,51 @@
+import pytest
+
+
+@pytest.fixture()
+def make_user() -> str:
+    return 'user'
+
+
+@pytest.fixture()
+def admin() -> str:
+    return 'admin'
+
+
+def test_local_bindings():
+    make_user = lambda: 'local'
+    assert make_user()
+
+
+def test_nested_bindings():
+    for admin in [str]:
+        admin()
+
+    def make_user() -> str:
+        return 'nested'
+
+    assert make_user()
+
+
+def test_imported_binding():
+    from os import getcwd as make_user
+
+    assert make_user()
+
+
+def test_binding_in_nested_scope():
+    def helper() -> str:
+        admin = str
+        return admin()
+
+    assert helper() and admin()
+
+
+def test_bindings_of_nested_scopes():
+    def helper() -> str:
+        admin = str
+        return admin()
+
+    call = lambda admin: admin()
+    names = [make_user() for make_user in [str]]
+
+    assert helper() and call(str) and names
@@ -0,0 +1,22 @@
+import pytest
+
+pytestmark = [pytest.mark.usefixtures('database', 'tmp_path'), pytest.mark.slow]
+
+
+@pytest.fixture()
+def database() -> str:
+    return 'sqlite://'
+
+
+@pytest.mark.usefixtures('databse')
+def test_function():
+    pass
+
+
+@pytest.mark.usefixtures('cleanup_env')
+class TestClass:
+    pytestmark = pytest.mark.usefixtures('missing_in_class')
+
+    @pytest.mark.usefixtures('database', 'missing_in_method')
+    def test_method(self):
+        pass
@@ -0,0 +1,8 @@
+from pathlib import Path
+
+import pytest
+
+
+@pytest.fixture()
+def tmp() -> Path:
+    return Path('/tmp')
@@ -0,0 +1,13 @@
+from typing import TYPE_CHECKING
+
+try:
+    import simplejson as json
+except ImportError:
+    import json
+
+if TYPE_CHECKING:
+    from pathlib import Path
+
+
+def test_tmp(tmp: Path):
+    assert json.dumps(str(tmp))
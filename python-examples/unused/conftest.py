@@ -0,0 +1,31 @@
+import pytest
+
+
+@pytest.fixture()
+def database() -> str:
+    return 'sqlite://'
+
+
+@pytest.fixture()
+def unused_client() -> str:
+    return 'client'
+
+
+@pytest.fixture(autouse=True)
+def setup_environment() -> None:
+    pass
+
+
+@pytest.fixture()
+def legacy_fixture() -> int:
+    return 1
+
+
+@pytest.fixture()
+def cleanup() -> None:
+    pass
+
+
+@pytest.fixture()
+def lazy_value() -> int:
+    return 2
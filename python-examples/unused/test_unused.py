@@ -0,0 +1,7 @@
+import pytest
+
+pytestmark = pytest.mark.usefixtures('cleanup')
+
+
+def test_database(database: str, request):
+    assert request.getfixturevalue('lazy_value') == 2
@@ -1,59 +1,298 @@
 use colored::*;
+use serde::Serialize;
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+use crate::{location::Span, render::paint, suggestions::FixtureSuggestion};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "type")]
 pub enum AnalysisError {
     FixtureMissingReturnType {
         fixture_name: String,
+        /// The type inferred from the fixture's body, if any.
+        suggested_type: Option<String>,
     },
     IncorrectArgumentType {
         function_name: String,
         argument_name: String,
         expected_type: String,
         provided_type: String,
+        #[serde(skip)]
+        span: Span,
     },
     MissingArgumentType {
         function_name: String,
         argument_name: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    IncorrectInferredArgumentType {
+        function_name: String,
+        argument_name: String,
+        inferred_type: String,
+        provided_type: String,
+        #[serde(skip)]
+        span: Span,
     },
     FixtureDoesNotExist {
         function_name: String,
         argument_name: String,
+        suggestions: Vec<FixtureSuggestion>,
+        #[serde(skip)]
+        span: Span,
+    },
+    IncorrectFixtureValueType {
+        function_name: String,
+        variable_name: String,
+        fixture_name: String,
+        expected_type: String,
+        provided_type: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    FixtureCalledDirectly {
+        function_name: String,
+        fixture_name: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    IncorrectStrategyType {
+        function_name: String,
+        argument_name: String,
+        expected_type: String,
+        provided_type: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    IncorrectMockArgumentType {
+        function_name: String,
+        argument_name: String,
+        #[serde(skip)]
+        span: Span,
+    },
+    DuplicateFixture {
+        fixture_name: String,
+        first_line: usize,
+        second_line: usize,
+    },
+    IncompatibleFixtureOverride {
+        fixture_name: String,
+        line: usize,
+        fixture_type: String,
+        overridden_type: String,
+        overridden_location: String,
+    },
+    UnusedFixture {
+        fixture_name: String,
+        line: usize,
+    },
+    UnsupportedAnnotation {
+        function_name: String,
+        annotation: String,
+        line: usize,
+        #[serde(skip)]
+        span: Span,
+    },
+    UnparsableFile {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    UnreadableFile {
+        reason: String,
+    },
+    UnusedSuppression {
+        line: usize,
+        codes: Vec<String>,
     },
-    UnparsableFile,
 }
 
-impl Display for AnalysisError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl AnalysisError {
+    pub fn severity(&self) -> Severity {
+        match self {
+            AnalysisError::IncompatibleFixtureOverride { .. }
+            | AnalysisError::UnusedSuppression { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl AnalysisError {
+    /// Returns the diagnostic's message, with a colored tag when `color` is set.
+    pub fn message(&self, color: bool) -> String {
+        let mut message = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = self.write_message(&mut message, color);
+        message
+    }
+
+    fn write_message(&self, f: &mut impl std::fmt::Write, color: bool) -> std::fmt::Result {
         match self {
             AnalysisError::FixtureDoesNotExist {
                 function_name,
                 argument_name,
-            } => write!(
-                f,
-                "{} Fixture {} used in function {} does not exist.",
-                "[FIXTURE_DOES_NOT_EXIST]".red(),
-                argument_name, function_name
-            ),
-            AnalysisError::FixtureMissingReturnType { fixture_name } => {
-                write!(f, "{} Fixture {} missing return type.", "[FIXTURE_MISSING_RETURN_TYPE]".red(), fixture_name)
+                suggestions,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} Fixture {} used in function {} does not exist.",
+                    paint("[FIXTURE_DOES_NOT_EXIST]".red(), color),
+                    argument_name, function_name
+                )?;
+                if !suggestions.is_empty() {
+                    let suggestions: Vec<String> = suggestions.iter().map(|s| s.to_string()).collect();
+                    write!(f, " Did you mean {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            AnalysisError::FixtureMissingReturnType { fixture_name, suggested_type } => {
+                write!(f, "{} Fixture {} missing return type.", paint("[FIXTURE_MISSING_RETURN_TYPE]".red(), color), fixture_name)?;
+                if let Some(suggested_type) = suggested_type {
+                    write!(f, " Its body suggests {}.", suggested_type)?;
+                }
+                Ok(())
             }
             AnalysisError::IncorrectArgumentType {
                 function_name,
                 argument_name,
                 expected_type,
                 provided_type,
+                ..
             } => write!(
                 f,
                 "{} Function's {} argument {} receives a fixture of type {}, but specified type is {}.",
-                "[INCORRECT_ARGUMENT_TYPE]".red(),
+                paint("[INCORRECT_ARGUMENT_TYPE]".red(), color),
                 function_name, argument_name, expected_type, provided_type
             ),
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name,
+                argument_name,
+                inferred_type,
+                provided_type,
+                ..
+            } => write!(
+                f,
+                "{} Function's {} argument {} receives a fixture of inferred type {}, but specified type is {}.",
+                paint("[INCORRECT_INFERRED_ARGUMENT_TYPE]".red(), color),
+                function_name, argument_name, inferred_type, provided_type
+            ),
             AnalysisError::MissingArgumentType {
                 function_name,
                 argument_name,
-            } => write!(f, "{} Function {} has no type specified for argument {}.", "[MISSING_ARGUMENT_TYPE]".red(), function_name, argument_name),
-            AnalysisError::UnparsableFile => write!(f, "{} Impossible to parse file's AST.", "[UNPARSABLE_FILE]".red()),
+                ..
+            } => write!(f, "{} Function {} has no type specified for argument {}.", paint("[MISSING_ARGUMENT_TYPE]".red(), color), function_name, argument_name),
+            AnalysisError::IncorrectFixtureValueType {
+                function_name,
+                variable_name,
+                fixture_name,
+                expected_type,
+                provided_type,
+                ..
+            } => write!(
+                f,
+                "{} Function's {} variable {} receives fixture {} of type {}, but specified type is {}.",
+                paint("[INCORRECT_FIXTURE_VALUE_TYPE]".red(), color),
+                function_name, variable_name, fixture_name, expected_type, provided_type
+            ),
+            AnalysisError::FixtureCalledDirectly {
+                function_name,
+                fixture_name,
+                ..
+            } => write!(
+                f,
+                "{} Function {} calls fixture {} directly, request it as an argument instead.",
+                paint("[FIXTURE_CALLED_DIRECTLY]".red(), color),
+                function_name, fixture_name
+            ),
+            AnalysisError::IncorrectStrategyType {
+                function_name,
+                argument_name,
+                expected_type,
+                provided_type,
+                ..
+            } => write!(
+                f,
+                "{} Function's {} argument {} receives values of type {} from hypothesis, but specified type is {}.",
+                paint("[INCORRECT_STRATEGY_TYPE]".red(), color),
+                function_name, argument_name, expected_type, provided_type
+            ),
+            AnalysisError::IncorrectMockArgumentType {
+                function_name,
+                argument_name,
+                ..
+            } => write!(
+                f,
+                "{} Function's {} argument {} receives a mock from mock.patch, but is not annotated as Mock, MagicMock or AsyncMock.",
+                paint("[INCORRECT_MOCK_ARGUMENT_TYPE]".red(), color),
+                function_name, argument_name
+            ),
+            AnalysisError::DuplicateFixture {
+                fixture_name,
+                first_line,
+                second_line,
+            } => write!(
+                f,
+                "{} Fixture {} defined on line {} is redefined on line {}.",
+                paint("[DUPLICATE_FIXTURE]".red(), color),
+                fixture_name, first_line, second_line
+            ),
+            AnalysisError::IncompatibleFixtureOverride {
+                fixture_name,
+                line,
+                fixture_type,
+                overridden_type,
+                overridden_location,
+            } => write!(
+                f,
+                "{} Fixture {} on line {} returns {}, but overrides fixture defined at {} returning {}.",
+                paint("[INCOMPATIBLE_FIXTURE_OVERRIDE]".yellow(), color),
+                fixture_name, line, fixture_type, overridden_location, overridden_type
+            ),
+            AnalysisError::UnusedFixture { fixture_name, line } => write!(f, "{} Fixture {} defined on line {} is never used.", paint("[UNUSED_FIXTURE]".red(), color), fixture_name, line),
+            AnalysisError::UnsupportedAnnotation {
+                function_name,
+                annotation,
+                line,
+                ..
+            } => write!(
+                f,
+                "{} Function {} has an annotation {} on line {} that is not a supported type.",
+                paint("[UNSUPPORTED_ANNOTATION]".red(), color),
+                function_name, annotation, line
+            ),
+            AnalysisError::UnparsableFile {
+                message,
+                line,
+                column,
+            } => write!(
+                f,
+                "{} Impossible to parse file's AST, {} on line {}, column {}.",
+                paint("[UNPARSABLE_FILE]".red(), color),
+                message, line, column
+            ),
+            AnalysisError::UnusedSuppression { line, codes } => {
+                let target = if codes.is_empty() {
+                    "any diagnostic".to_owned()
+                } else {
+                    codes.join(", ")
+                };
+                write!(f, "{} Suppression comment on line {} does not suppress {}.", paint("[UNUSED_SUPPRESSION]".yellow(), color), line, target)
+            }
+            AnalysisError::UnreadableFile { reason } => write!(f, "{} Unable to read file: {}.", paint("[UNREADABLE_FILE]".red(), color), reason),
         }
     }
 }
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_message(f, true)
+    }
+}
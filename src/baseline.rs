@@ -0,0 +1,241 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis_error::AnalysisError, files::parsed_python_file::ParsedPythonFile};
+
+const VERSION: u32 = 1;
+
+/// A recorded diagnostic. It is identified by the symbols it is about rather than by its line, so
+/// that it still matches once the code around it moves.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub filename: String,
+    pub code: String,
+    pub symbol: String,
+}
+
+/// The diagnostics that existed when the baseline was written, which are no longer reported.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub diagnostics: Vec<BaselineEntry>,
+}
+
+impl AnalysisError {
+    /// The symbols a diagnostic is about, such as `test_user::user` for an argument. Diagnostics
+    /// about the whole file have an empty symbol.
+    pub fn symbol(&self) -> String {
+        match self {
+            AnalysisError::IncorrectArgumentType {
+                function_name,
+                argument_name,
+                ..
+            }
+            | AnalysisError::MissingArgumentType {
+                function_name,
+                argument_name,
+                ..
+            }
+            | AnalysisError::IncorrectInferredArgumentType {
+                function_name,
+                argument_name,
+                ..
+            }
+            | AnalysisError::FixtureDoesNotExist {
+                function_name,
+                argument_name,
+                ..
+            }
+            | AnalysisError::IncorrectStrategyType {
+                function_name,
+                argument_name,
+                ..
+            }
+            | AnalysisError::IncorrectMockArgumentType {
+                function_name,
+                argument_name,
+                ..
+            } => format!("{}::{}", function_name, argument_name),
+            AnalysisError::IncorrectFixtureValueType {
+                function_name,
+                variable_name,
+                ..
+            } => format!("{}::{}", function_name, variable_name),
+            AnalysisError::FixtureCalledDirectly {
+                function_name,
+                fixture_name,
+                ..
+            } => format!("{}::{}", function_name, fixture_name),
+            AnalysisError::UnsupportedAnnotation {
+                function_name,
+                annotation,
+                ..
+            } => format!("{}::{}", function_name, annotation),
+            AnalysisError::FixtureMissingReturnType { fixture_name, .. }
+            | AnalysisError::DuplicateFixture { fixture_name, .. }
+            | AnalysisError::IncompatibleFixtureOverride { fixture_name, .. }
+            | AnalysisError::UnusedFixture { fixture_name, .. } => fixture_name.clone(),
+            AnalysisError::UnusedSuppression { codes, .. } => codes.join(","),
+            AnalysisError::UnparsableFile { .. } | AnalysisError::UnreadableFile { .. } => {
+                String::new()
+            }
+        }
+    }
+}
+
+fn normalize_filename(filename: &str) -> String {
+    filename
+        .strip_prefix("./")
+        .unwrap_or(filename)
+        .replace('\\', "/")
+}
+
+fn entry(filename: &str, err: &AnalysisError) -> BaselineEntry {
+    BaselineEntry {
+        filename: normalize_filename(filename),
+        code: err.rule().code().to_owned(),
+        symbol: err.symbol(),
+    }
+}
+
+impl Baseline {
+    /// Records every diagnostic of the files.
+    pub fn new(files: &[ParsedPythonFile]) -> Self {
+        let mut diagnostics: Vec<BaselineEntry> = files
+            .iter()
+            .flat_map(|file| {
+                file.errors
+                    .iter()
+                    .map(|err| entry(&file.file.filename, err))
+            })
+            .collect();
+        diagnostics.sort();
+
+        Baseline {
+            version: VERSION,
+            diagnostics,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read baseline {}: {}", path.display(), err))?;
+        let baseline: Baseline = serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid baseline {}: {}", path.display(), err))?;
+
+        if baseline.version != VERSION {
+            return Err(format!(
+                "Unsupported baseline version {} in {}.",
+                baseline.version,
+                path.display()
+            ));
+        }
+        Ok(baseline)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, content + "\n")
+            .map_err(|err| format!("Unable to write baseline {}: {}", path.display(), err))
+    }
+
+    /// Removes the diagnostics recorded in the baseline from the files. A recorded entry hides
+    /// one diagnostic, so a symbol gaining a second identical diagnostic still reports it.
+    pub fn apply(&self, files: &mut [ParsedPythonFile]) {
+        let mut remaining: HashMap<&BaselineEntry, usize> = HashMap::new();
+        for diagnostic in &self.diagnostics {
+            *remaining.entry(diagnostic).or_default() += 1;
+        }
+
+        for file in files.iter_mut() {
+            let filename = file.file.filename.clone();
+            file.errors
+                .retain(|err| match remaining.get_mut(&entry(&filename, err)) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        false
+                    }
+                    _ => true,
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        analysis_error::AnalysisError,
+        files::{parsed_python_file::ParsedPythonFile, python_file::PythonFile},
+        location::Span,
+    };
+
+    use super::{Baseline, BaselineEntry};
+
+    fn parsed_file(errors: Vec<AnalysisError>) -> ParsedPythonFile {
+        let file = PythonFile {
+            content: String::new(),
+            filename: "./tests/test_file.py".to_owned(),
+        };
+        ParsedPythonFile::empty(file, errors)
+    }
+
+    fn missing_type(function_name: &str, argument_name: &str) -> AnalysisError {
+        AnalysisError::MissingArgumentType {
+            function_name: function_name.to_owned(),
+            argument_name: argument_name.to_owned(),
+            span: Span::default(),
+        }
+    }
+
+    #[test]
+    fn assert_baseline_entries() {
+        let files = vec![parsed_file(vec![
+            missing_type("test_b", "user"),
+            AnalysisError::UnusedFixture {
+                fixture_name: "user".to_owned(),
+                line: 3,
+            },
+        ])];
+
+        assert_eq!(
+            Baseline::new(&files).diagnostics,
+            vec![
+                BaselineEntry {
+                    filename: "tests/test_file.py".to_owned(),
+                    code: "PTC003".to_owned(),
+                    symbol: "test_b::user".to_owned(),
+                },
+                BaselineEntry {
+                    filename: "tests/test_file.py".to_owned(),
+                    code: "PTC011".to_owned(),
+                    symbol: "user".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn assert_apply_baseline() {
+        let baseline = Baseline::new(&[parsed_file(vec![
+            missing_type("test_a", "user"),
+            missing_type("test_b", "user"),
+        ])]);
+
+        let mut files = vec![parsed_file(vec![
+            missing_type("test_b", "user"),
+            missing_type("test_b", "user"),
+            missing_type("test_c", "user"),
+            missing_type("test_a", "user"),
+        ])];
+        baseline.apply(&mut files);
+
+        assert_eq!(
+            files[0].errors,
+            vec![
+                missing_type("test_b", "user"),
+                missing_type("test_c", "user")
+            ]
+        );
+    }
+}
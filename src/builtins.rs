@@ -0,0 +1,27 @@
+/// Fixtures provided by pytest itself, which are available in every test module.
+pub const BUILTIN_FIXTURES: &[&str] = &[
+    "cache",
+    "capfd",
+    "capfdbinary",
+    "caplog",
+    "capsys",
+    "capsysbinary",
+    "doctest_namespace",
+    "monkeypatch",
+    "pytestconfig",
+    "pytester",
+    "record_property",
+    "record_testsuite_property",
+    "record_xml_attribute",
+    "recwarn",
+    "request",
+    "testdir",
+    "tmp_path",
+    "tmp_path_factory",
+    "tmpdir",
+    "tmpdir_factory",
+];
+
+pub fn is_builtin_fixture(name: &str) -> bool {
+    BUILTIN_FIXTURES.contains(&name)
+}
@@ -1,69 +1,467 @@
 use crate::{
     analysis_error::AnalysisError,
+    builtins::is_builtin_fixture,
     files::parsed_python_file::ParsedPythonFile,
-    functions::{get_argument_annotation, get_return_annotation},
+    fixture_index::{FixtureDefinition, FixtureIndex},
+    functions::{get_duplicate_fixtures, get_fixture_arguments, get_functions},
+    hypothesis::{get_given_bindings, infer_strategy_type, qualify_generated_type},
+    imports::{describe_mismatch, ResolvedAnnotation},
+    inference::{infer_fixture_type, is_assignable},
+    location::Span,
+    mocks::{get_mock_arguments, is_mock_annotation},
+    nodes::{get_pytestmark, get_usefixtures, FixtureUtil},
+    rules::Rule,
+    settings::Settings,
+    suggestions::suggest_fixtures,
+    usages::{
+        get_argument_names, get_bound_names, get_called_name_nodes, get_fixture_value_lookups,
+    },
 };
-use rustpython_ast::StmtFunctionDef;
-use std::collections::HashMap;
+use rustpython_ast::{ArgWithDefault, Expr, Ranged, Stmt, StmtFunctionDef};
+use rustpython_parser_vendored::text_size::TextSize;
+use std::collections::{HashMap, HashSet};
 
+fn is_known_fixture(name: &str, fixtures: &HashMap<String, FixtureDefinition>) -> bool {
+    fixtures.contains_key(name) || is_builtin_fixture(name)
+}
+
+/// Returns the span of an argument's annotation, or of the argument itself when it has none.
+fn argument_span(file: &ParsedPythonFile, func: &StmtFunctionDef, arg: &ArgWithDefault) -> Span {
+    let range = match arg.def.annotation.as_deref() {
+        Some(annotation) => annotation.range(),
+        None => arg.def.range(),
+    };
+    Span::new(&file.file, range, func.start())
+}
+
+/// Resolves an annotation written in `func`, reporting it when it is not a type the checker
+/// understands.
+fn resolve_annotation(
+    func: &StmtFunctionDef,
+    annotation: &Expr,
+    file: &ParsedPythonFile,
+    errors: &mut Vec<AnalysisError>,
+) -> Option<ResolvedAnnotation> {
+    let resolved = file.imports.resolve_annotation(annotation);
+
+    if resolved.is_none() {
+        errors.push(AnalysisError::UnsupportedAnnotation {
+            function_name: func.name.to_string(),
+            annotation: file.file.content[annotation.range()].to_owned(),
+            line: file.file.line_number(annotation.start()),
+            span: Span::new(&file.file, annotation.range(), func.start()),
+        });
+    }
+
+    resolved
+}
+
+/// Checks the arguments of a function against the fixtures they receive. A fixture without a
+/// return annotation is checked against the type inferred from its body, if the index has one.
 pub fn check_function_arguments(
     func: &StmtFunctionDef,
-    fixtures: &HashMap<String, StmtFunctionDef>,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
 ) -> Vec<AnalysisError> {
     let function_name = &func.name;
 
     let mut errors: Vec<AnalysisError> = Vec::new();
 
-    for arg in func.args.args.iter() {
+    for arg in get_fixture_arguments(func) {
         let arg_name = arg.def.arg.to_string();
-        let arg_annotation = get_argument_annotation(arg);
+        let span = argument_span(file, func, arg);
+        let arg_annotation = match arg.def.annotation.as_deref() {
+            Some(annotation) => match resolve_annotation(func, annotation, file, &mut errors) {
+                Some(resolved) => Some(resolved),
+                None => continue,
+            },
+            None => None,
+        };
 
         match arg_annotation {
             Some(arg_annotation) => {
                 let fixture = fixtures.get(&arg_name);
                 match fixture {
-                    Some(fixture) => {
-                        if let Some(fixture_annotation) = get_return_annotation(fixture) {
-                            if fixture_annotation != arg_annotation {
+                    Some(fixture) => match fixture.return_annotation() {
+                        Some(fixture_annotation) => {
+                            if fixture_annotation.qualified != arg_annotation.qualified {
+                                let (expected_type, provided_type) =
+                                    describe_mismatch(fixture_annotation, arg_annotation);
                                 errors.push(AnalysisError::IncorrectArgumentType {
                                     function_name: function_name.to_string(),
                                     argument_name: arg_name,
-                                    expected_type: fixture_annotation,
-                                    provided_type: arg_annotation,
+                                    expected_type,
+                                    provided_type,
+                                    span,
+                                })
+                            }
+                        }
+                        None => {
+                            let Some(inferred) = index.inferred_type(fixture) else {
+                                continue;
+                            };
+                            if !is_assignable(&inferred.qualified, &arg_annotation.qualified) {
+                                let (inferred_type, provided_type) =
+                                    describe_mismatch(inferred.clone(), arg_annotation);
+                                errors.push(AnalysisError::IncorrectInferredArgumentType {
+                                    function_name: function_name.to_string(),
+                                    argument_name: arg_name,
+                                    inferred_type,
+                                    provided_type,
+                                    span,
                                 })
                             }
                         }
-                    }
+                    },
+                    None if is_builtin_fixture(&arg_name) => {}
                     None => errors.push(AnalysisError::FixtureDoesNotExist {
                         function_name: function_name.to_string(),
+                        suggestions: suggest_fixtures(&arg_name, fixtures),
                         argument_name: arg_name,
+                        span,
                     }),
                 }
             }
             None => errors.push(AnalysisError::MissingArgumentType {
                 function_name: function_name.to_string(),
                 argument_name: arg_name,
+                span,
             }),
         }
     }
     errors
 }
 
-pub fn check_file(file: &ParsedPythonFile) -> Vec<AnalysisError> {
+/// Checks the `request.getfixturevalue("name")` calls in a function's body: the fixture has to
+/// exist and, when the value is assigned to an annotated variable, its type has to match.
+pub fn check_fixture_values(
+    func: &StmtFunctionDef,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    file: &ParsedPythonFile,
+) -> Vec<AnalysisError> {
+    let function_name = &func.name;
+    let mut errors = Vec::new();
+
+    for lookup in get_fixture_value_lookups(&func.body) {
+        if !is_known_fixture(&lookup.fixture_name, fixtures) {
+            errors.push(AnalysisError::FixtureDoesNotExist {
+                function_name: function_name.to_string(),
+                suggestions: suggest_fixtures(&lookup.fixture_name, fixtures),
+                argument_name: lookup.fixture_name,
+                span: Span::new(&file.file, lookup.range, func.start()),
+            });
+            continue;
+        }
+
+        let Some((variable_name, annotation)) = lookup.target else {
+            continue;
+        };
+        let annotation_range = annotation.range();
+        let fixture_annotation = fixtures
+            .get(&lookup.fixture_name)
+            .and_then(|f| f.return_annotation());
+
+        if let (Some(fixture_annotation), Some(annotation)) = (
+            fixture_annotation,
+            resolve_annotation(func, &annotation, file, &mut errors),
+        ) {
+            if fixture_annotation.qualified != annotation.qualified {
+                let (expected_type, provided_type) =
+                    describe_mismatch(fixture_annotation, annotation);
+                errors.push(AnalysisError::IncorrectFixtureValueType {
+                    function_name: function_name.to_string(),
+                    variable_name,
+                    fixture_name: lookup.fixture_name,
+                    expected_type,
+                    provided_type,
+                    span: Span::new(&file.file, annotation_range, func.start()),
+                })
+            }
+        }
+    }
+
+    errors
+}
+
+/// Reports calls to fixture functions made as if they were regular functions. Names shadowed by
+/// the function's own arguments, e.g. a requested factory fixture, or bound in its body are not
+/// reported, nor are the calls a nested function, lambda or comprehension makes to its own names.
+pub fn check_fixture_calls(
+    func: &StmtFunctionDef,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    file: &ParsedPythonFile,
+) -> Vec<AnalysisError> {
+    let mut shadowed: HashSet<String> = get_bound_names(&func.body);
+    shadowed.extend(get_argument_names(&func.args));
+    let mut reported = HashSet::new();
+
+    get_called_name_nodes(&func.body)
+        .into_iter()
+        .filter(|name| {
+            fixtures.contains_key(name.id.as_str()) && !shadowed.contains(name.id.as_str())
+        })
+        .filter(|name| reported.insert(name.id.to_string()))
+        .map(|name| AnalysisError::FixtureCalledDirectly {
+            function_name: func.name.to_string(),
+            fixture_name: name.id.to_string(),
+            span: Span::new(&file.file, name.range(), func.start()),
+        })
+        .collect()
+}
+
+/// Checks the arguments bound by hypothesis's `@given` against the type generated by their strategy,
+/// when it can be inferred.
+pub fn check_given_arguments(
+    func: &StmtFunctionDef,
+    file: &ParsedPythonFile,
+) -> Vec<AnalysisError> {
+    let mut errors = Vec::new();
+
+    for (arg, strategy) in get_given_bindings(func) {
+        let (Some(generated), Some(annotation)) =
+            (infer_strategy_type(strategy), arg.def.annotation.as_deref())
+        else {
+            continue;
+        };
+        let span = argument_span(file, func, arg);
+        let (Some(qualified), Some(annotation)) = (
+            qualify_generated_type(&generated, &file.imports),
+            file.imports.resolve_annotation(annotation),
+        ) else {
+            continue;
+        };
+
+        if !is_assignable(&qualified, &annotation.qualified) {
+            let generated = ResolvedAnnotation {
+                written: generated,
+                qualified,
+            };
+            let (expected_type, provided_type) = describe_mismatch(generated, annotation);
+            errors.push(AnalysisError::IncorrectStrategyType {
+                function_name: func.name.to_string(),
+                argument_name: arg.def.arg.to_string(),
+                expected_type,
+                provided_type,
+                span,
+            })
+        }
+    }
+
+    errors
+}
+
+/// Checks that the arguments receiving a mock from a `mock.patch` decorator are annotated with
+/// one of the `unittest.mock` classes.
+pub fn check_mock_arguments(func: &StmtFunctionDef, file: &ParsedPythonFile) -> Vec<AnalysisError> {
+    get_mock_arguments(func)
+        .into_iter()
+        .filter(|arg| {
+            !arg.def
+                .annotation
+                .as_deref()
+                .is_some_and(is_mock_annotation)
+        })
+        .map(|arg| AnalysisError::IncorrectMockArgumentType {
+            function_name: func.name.to_string(),
+            argument_name: arg.def.arg.to_string(),
+            span: argument_span(file, func, arg),
+        })
+        .collect()
+}
+
+fn check_usefixtures_marks(
+    owner_name: &str,
+    owner_start: TextSize,
+    marks: &[Expr],
+    fixtures: &HashMap<String, FixtureDefinition>,
+    file: &ParsedPythonFile,
+) -> Vec<AnalysisError> {
+    marks
+        .iter()
+        .flat_map(|mark| {
+            get_usefixtures(mark)
+                .into_iter()
+                .map(move |name| (mark, name))
+        })
+        .filter(|(_, name)| !is_known_fixture(name, fixtures))
+        .map(|(mark, name)| AnalysisError::FixtureDoesNotExist {
+            function_name: owner_name.to_owned(),
+            suggestions: suggest_fixtures(&name, fixtures),
+            argument_name: name,
+            span: Span::new(&file.file, mark.range(), owner_start),
+        })
+        .collect()
+}
+
+/// Checks that every fixture named in a `usefixtures` mark, whether it decorates a function or a
+/// class or is part of a `pytestmark` assignment, is known.
+pub fn check_usefixtures(
+    stmts: &[Stmt],
+    owner_name: &str,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    file: &ParsedPythonFile,
+) -> Vec<AnalysisError> {
+    let mut errors = Vec::new();
+
+    if let Some(pytestmark) = get_pytestmark(stmts) {
+        errors.extend(check_usefixtures_marks(
+            owner_name,
+            pytestmark.start(),
+            std::slice::from_ref(pytestmark),
+            fixtures,
+            file,
+        ));
+    }
+
+    for stmt in stmts {
+        match stmt {
+            Stmt::FunctionDef(func) => errors.extend(check_usefixtures_marks(
+                &func.name,
+                func.start(),
+                &func.decorator_list,
+                fixtures,
+                file,
+            )),
+            Stmt::ClassDef(class) => {
+                errors.extend(check_usefixtures_marks(
+                    &class.name,
+                    class.start(),
+                    &class.decorator_list,
+                    fixtures,
+                    file,
+                ));
+                errors.extend(check_usefixtures(&class.body, &class.name, fixtures, file));
+            }
+            _ => {}
+        }
+    }
+
+    errors
+}
+
+/// Reports fixtures defined twice in the same module, and fixtures overriding a conftest fixture
+/// with an incompatible return type.
+pub fn check_fixture_definitions(
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+) -> Vec<AnalysisError> {
     let mut errors = Vec::new();
 
+    for (first, second) in get_duplicate_fixtures(&get_functions(&file.body)) {
+        errors.push(AnalysisError::DuplicateFixture {
+            fixture_name: second.name.to_string(),
+            first_line: file.file.line_number(first.start()),
+            second_line: file.file.line_number(second.start()),
+        })
+    }
+
     for (fixture_name, func) in file.fixtures.iter() {
-        if get_return_annotation(func).is_none() {
-            errors.push(AnalysisError::FixtureMissingReturnType {
+        let Some(overridden) = index.overridden_fixture(file, fixture_name) else {
+            continue;
+        };
+
+        let fixture_annotation = FixtureDefinition::new(func, file).return_annotation();
+
+        if let (Some(fixture_annotation), Some(overridden_annotation)) =
+            (fixture_annotation, overridden.return_annotation())
+        {
+            if fixture_annotation.qualified != overridden_annotation.qualified {
+                let (fixture_type, overridden_type) =
+                    describe_mismatch(fixture_annotation, overridden_annotation);
+                errors.push(AnalysisError::IncompatibleFixtureOverride {
+                    fixture_name: fixture_name.clone(),
+                    line: file.file.line_number(func.start()),
+                    fixture_type,
+                    overridden_type,
+                    overridden_location: overridden.location(),
+                })
+            }
+        }
+    }
+
+    errors
+}
+
+pub fn check_file(
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+    settings: &Settings,
+) -> Vec<AnalysisError> {
+    let mut errors = Vec::new();
+    let fixtures = index.visible_fixtures(file);
+
+    for (fixture_name, func) in file.fixtures.iter() {
+        match func.returns.as_deref() {
+            Some(returns) => {
+                resolve_annotation(func, returns, file, &mut errors);
+            }
+            None => errors.push(AnalysisError::FixtureMissingReturnType {
                 fixture_name: fixture_name.clone(),
-            })
+                suggested_type: infer_fixture_type(func, file, index, &fixtures)
+                    .map(|inferred| inferred.to_string()),
+            }),
         }
-        errors.extend(check_function_arguments(func, &file.fixtures))
+
+        // A fixture requesting its own name receives the fixture it overrides.
+        let mut parent_fixtures = fixtures.clone();
+        match index.overridden_fixture(file, fixture_name) {
+            Some(overridden) => parent_fixtures.insert(fixture_name.clone(), overridden.clone()),
+            None => parent_fixtures.remove(fixture_name),
+        };
+
+        errors.extend(check_function_arguments(
+            func,
+            &parent_fixtures,
+            file,
+            index,
+        ));
+        errors.extend(check_fixture_values(func, &fixtures, file));
+        errors.extend(check_fixture_calls(func, &fixtures, file));
     }
 
     for (_test_case_name, func) in file.test_cases.iter() {
-        errors.extend(check_function_arguments(func, &file.fixtures))
+        errors.extend(check_function_arguments(func, &fixtures, file, index));
+        errors.extend(check_given_arguments(func, file));
+        errors.extend(check_fixture_values(func, &fixtures, file));
+        errors.extend(check_fixture_calls(func, &fixtures, file));
+    }
+
+    if settings.is_rule_active(Rule::IncorrectMockArgumentType) {
+        for func in file.fixtures.values().chain(file.test_cases.values()) {
+            errors.extend(check_mock_arguments(func, file));
+        }
     }
 
+    errors.extend(check_usefixtures(&file.body, "pytestmark", &fixtures, file));
+    errors.extend(check_fixture_definitions(file, index));
+
     errors
 }
+
+/// Reports the fixtures of `file` that do not appear in `requested`, which should contain the
+/// fixture names requested across the whole project.
+pub fn check_unused_fixtures(
+    file: &ParsedPythonFile,
+    requested: &HashSet<String>,
+    settings: &Settings,
+) -> Vec<AnalysisError> {
+    let mut unused: Vec<(&String, &StmtFunctionDef)> = file
+        .fixtures
+        .iter()
+        .filter(|(name, func)| {
+            !requested.contains(*name)
+                && !func.is_autouse_fixture()
+                && !settings.unused_fixtures_allowlist.contains(name)
+        })
+        .collect();
+    unused.sort_by_key(|(_, func)| func.start());
+
+    unused
+        .into_iter()
+        .map(|(name, func)| AnalysisError::UnusedFixture {
+            fixture_name: name.clone(),
+            line: file.file.line_number(func.start()),
+        })
+        .collect()
+}
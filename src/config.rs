@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::rules::RuleSelection;
+
+/// The `[tool.pytest-typechecker]` table of a `pyproject.toml` file.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub select: Option<Vec<String>>,
+    pub extend_select: Vec<String>,
+    pub ignore: Vec<String>,
+    pub per_file_ignores: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PyProject {
+    #[serde(default)]
+    tool: Tools,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Tools {
+    #[serde(rename = "pytest-typechecker")]
+    pytest_typechecker: Option<Config>,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let pyproject: PyProject = toml::from_str(content).map_err(|err| err.to_string())?;
+        Ok(pyproject.tool.pytest_typechecker.unwrap_or_default())
+    }
+
+    /// Reads the configuration from a `pyproject.toml` file. A missing file gives the default
+    /// configuration.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Config::parse(&content)
+                .map_err(|err| format!("Invalid configuration in {}: {}", path.display(), err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(format!("Unable to read {}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn into_rule_selection(self) -> RuleSelection {
+        RuleSelection {
+            select: self.select,
+            extend_select: self.extend_select,
+            ignore: self.ignore,
+            per_file_ignores: self.per_file_ignores.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    #[test]
+    fn assert_parse_config() {
+        let config = Config::parse(
+            r#"
+[project]
+name = "app"
+
+[tool.pytest-typechecker]
+extend-select = ["PTC011"]
+ignore = ["PTC003"]
+
+[tool.pytest-typechecker.per-file-ignores]
+"tests/legacy/*.py" = ["PTC002", "PTC004"]
+"#,
+        )
+        .unwrap();
+        let rules = config.into_rule_selection();
+
+        assert_eq!(rules.select, None);
+        assert_eq!(rules.extend_select, vec!["PTC011"]);
+        assert_eq!(rules.ignore, vec!["PTC003"]);
+        assert_eq!(
+            rules.per_file_ignores,
+            vec![(
+                "tests/legacy/*.py".to_owned(),
+                vec!["PTC002".to_owned(), "PTC004".to_owned()]
+            )]
+        );
+    }
+
+    #[test]
+    fn assert_parse_config_without_table() {
+        assert_eq!(Config::parse("[project]\n"), Ok(Config::default()));
+        assert!(Config::parse("[tool.pytest-typechecker]\nselct = []\n").is_err());
+    }
+}
@@ -1,22 +1,114 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use python_file::PythonFile;
 pub mod parsed_python_file;
 pub mod python_file;
-use glob::glob;
-
-pub fn read_file(file: &PathBuf) -> PythonFile {
-    let filename = file
-        .as_os_str()
-        .to_str()
-        .expect("Invalid filename.")
-        .to_string();
-    let content: String = match fs::read_to_string(file) {
-        Ok(content) => content,
-        Err(_) => panic!("Unable to read file."),
+use walkdir::WalkDir;
+
+use crate::analysis_error::AnalysisError;
+use parsed_python_file::ParsedPythonFile;
+
+/// Returns the encoding declared by a PEP 263 coding cookie, which has to be a comment on one of
+/// the first two lines, e.g. `# -*- coding: latin-1 -*-`.
+fn get_coding_cookie(content: &[u8]) -> Option<String> {
+    for line in content.split(|b| *b == b'\n').take(2) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_start_matches([' ', '\t', '\x0c']);
+        if !line.starts_with('#') {
+            continue;
+        }
+
+        let Some(position) = line.find("coding") else {
+            continue;
+        };
+        let rest = &line[position + "coding".len()..];
+        let Some(rest) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let encoding: String = rest
+            .trim_start_matches([' ', '\t'])
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect();
+
+        if !encoding.is_empty() {
+            return Some(encoding);
+        }
+    }
+
+    None
+}
+
+/// Decodes a Python source file the way the interpreter does: UTF-8 by default, or the encoding
+/// declared by its coding cookie. Only the UTF-8, Latin-1 and ASCII encodings are supported.
+pub fn decode_source(content: &[u8]) -> Result<String, String> {
+    let (content, has_bom) = match content.strip_prefix(b"\xef\xbb\xbf") {
+        Some(content) => (content, true),
+        None => (content, false),
     };
+    let encoding = get_coding_cookie(content)
+        .map(|e| e.to_lowercase().replace('_', "-"))
+        .unwrap_or_else(|| "utf-8".to_owned());
+
+    match encoding.as_str() {
+        "utf-8" | "utf8" => String::from_utf8(content.to_vec())
+            .map_err(|_| "the content is not valid UTF-8".to_owned()),
+        _ if has_bom => Err(format!(
+            "the file starts with a UTF-8 byte order mark, but declares encoding {}",
+            encoding
+        )),
+        "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "iso-latin-1" | "l1" => {
+            Ok(content.iter().map(|b| *b as char).collect())
+        }
+        "ascii" | "us-ascii" => match content.is_ascii() {
+            true => Ok(content.iter().map(|b| *b as char).collect()),
+            false => Err("the content is not valid ASCII".to_owned()),
+        },
+        _ => Err(format!("encoding {} is not supported", encoding)),
+    }
+}
+
+fn describe_io_error(file: &Path, err: io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => "permission denied".to_owned(),
+        io::ErrorKind::NotFound if file.symlink_metadata().is_ok() => {
+            "broken symbolic link".to_owned()
+        }
+        io::ErrorKind::NotFound => "no such file".to_owned(),
+        _ => err.to_string(),
+    }
+}
 
-    PythonFile { content, filename }
+/// Reads a file, returning why it cannot be read on failure.
+pub fn read_file(file: &Path) -> Result<PythonFile, String> {
+    let filename = file.to_string_lossy().to_string();
+    let content = fs::read(file)
+        .map_err(|err| describe_io_error(file, err))
+        .and_then(|content| decode_source(&content))?;
+
+    Ok(PythonFile { content, filename })
+}
+
+/// Reads and parses a file. A file that cannot be read is returned without content, carrying the
+/// reason as its only diagnostic.
+pub fn load_file(file: &Path) -> ParsedPythonFile {
+    match read_file(file) {
+        Ok(file) => PythonFile::parse(file),
+        Err(reason) => ParsedPythonFile::empty(
+            PythonFile {
+                content: String::new(),
+                filename: file.to_string_lossy().to_string(),
+            },
+            vec![AnalysisError::UnreadableFile { reason }],
+        ),
+    }
+}
+
+fn is_python_file(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "py")
 }
 
 pub fn get_files_list(provided: &[PathBuf], recursive: bool) -> Result<Vec<PathBuf>, String> {
@@ -24,33 +116,42 @@ pub fn get_files_list(provided: &[PathBuf], recursive: bool) -> Result<Vec<PathB
         .iter()
         .flat_map(|v| {
             if v.is_dir() {
-                let pattern = if recursive {
-                    v.join("**").join("*.py")
+                let walker = WalkDir::new(v)
+                    .min_depth(1)
+                    .follow_links(true)
+                    .sort_by_file_name();
+                let walker = if recursive {
+                    walker
                 } else {
-                    v.join("*.py")
+                    walker.max_depth(1)
                 };
 
-                let pattern = pattern.to_str().unwrap().to_string();
-
-                glob(&pattern)
-                    .expect("Incorrect glob")
-                    .filter_map(|f| f.ok())
+                walker
+                    .into_iter()
+                    .filter_map(|entry| match entry {
+                        Ok(entry) => (!entry.file_type().is_dir()).then(|| entry.into_path()),
+                        // Broken symbolic links are kept, reading them reports a diagnostic.
+                        Err(err) => err.path().map(Path::to_path_buf),
+                    })
+                    .filter(|path| is_python_file(path))
                     .collect()
             } else {
                 vec![v.clone()]
             }
         })
         .map(|f| {
-            if f.exists() {
+            if f.symlink_metadata().is_ok() {
                 Ok(f)
             } else {
-                Err(format!("File {} does not exist.", f.to_str().unwrap()))
+                Err(format!("File {} does not exist.", f.display()))
             }
         })
         .collect()
 }
 
 #[cfg(test)]
+// The original tests predate the deprecation of `TempDir::into_path` and these lints.
+#[allow(deprecated, clippy::useless_vec, clippy::cloned_ref_to_slice_refs)]
 mod tests {
 
     use std::{collections::HashSet, fs::File, path::Path};
@@ -153,6 +254,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn assert_decode_source() {
+        assert_eq!(decode_source(b"x = 1\n"), Ok("x = 1\n".to_owned()));
+        assert_eq!(
+            decode_source(b"\xef\xbb\xbfx = '\xc3\xa9'\n"),
+            Ok("x = '\u{e9}'\n".to_owned())
+        );
+        assert_eq!(
+            decode_source(b"#!/usr/bin/env python\n# -*- coding: latin-1 -*-\nx = '\xe9'\n"),
+            Ok("#!/usr/bin/env python\n# -*- coding: latin-1 -*-\nx = '\u{e9}'\n".to_owned())
+        );
+        assert!(decode_source(b"x = '\xe9'\n").is_err());
+        assert!(decode_source(b"# coding=ascii\nx = '\xe9'\n").is_err());
+        assert!(decode_source(b"# vim: set fileencoding=cp1251 :\n").is_err());
+        assert!(decode_source(b"x = 1\n\n# coding: latin-1\n").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn assert_broken_symlink() -> anyhow::Result<()> {
+        let base_dir: PathBuf = generate_test_directory(true)?.keep();
+        std::os::unix::fs::symlink(base_dir.join("missing.py"), base_dir.join("broken.py"))?;
+
+        let output: Vec<PathBuf> = get_files_list(std::slice::from_ref(&base_dir), false).unwrap();
+        assert_eq!(output, vec![base_dir.join("broken.py")]);
+
+        assert_eq!(read_file(&output[0]).unwrap_err(), "broken symbolic link");
+
+        Ok(())
+    }
+
     #[test]
     fn assert_files_empty_dir() -> anyhow::Result<()> {
         let base_dir: PathBuf = generate_test_directory(true)?.into_path();
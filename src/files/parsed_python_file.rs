@@ -1,7 +1,9 @@
 use crate::analysis_error::AnalysisError;
 use crate::files::python_file::PythonFile;
+use crate::imports::ImportTable;
+use crate::suppressions::Suppressions;
 use colored::Colorize;
-use rustpython_ast::StmtFunctionDef;
+use rustpython_ast::{Stmt, StmtFunctionDef};
 use std::collections::HashMap;
 use std::fmt::Display;
 
@@ -11,6 +13,24 @@ pub struct ParsedPythonFile {
     pub errors: Vec<AnalysisError>,
     pub fixtures: HashMap<String, StmtFunctionDef>,
     pub test_cases: HashMap<String, StmtFunctionDef>,
+    pub body: Vec<Stmt>,
+    pub imports: ImportTable,
+    pub suppressions: Suppressions,
+}
+
+impl ParsedPythonFile {
+    /// Returns a file without any definitions, for files that could not be read or parsed.
+    pub fn empty(file: PythonFile, errors: Vec<AnalysisError>) -> Self {
+        ParsedPythonFile {
+            file,
+            errors,
+            fixtures: HashMap::new(),
+            test_cases: HashMap::new(),
+            body: Vec::new(),
+            imports: ImportTable::default(),
+            suppressions: Suppressions::default(),
+        }
+    }
 }
 
 impl Display for ParsedPythonFile {
@@ -18,10 +38,27 @@ impl Display for ParsedPythonFile {
         for err in &self.errors {
             writeln!(
                 f,
-                "{}: {}",
+                "{}: {} {}",
                 self.file.filename.truecolor(255, 255, 255),
+                err.rule().code().bold(),
                 err
-            )?
+            )?;
+
+            if let AnalysisError::UnparsableFile { line, column, .. } = err {
+                if let Some(source) = self.file.line(*line) {
+                    let gutter = line.to_string();
+                    writeln!(f, "{} |", " ".repeat(gutter.len()).blue())?;
+                    writeln!(f, "{} {} {}", gutter.blue(), "|".blue(), source)?;
+                    writeln!(
+                        f,
+                        "{} {} {}{}",
+                        " ".repeat(gutter.len()),
+                        "|".blue(),
+                        " ".repeat(column.saturating_sub(1)),
+                        "^".red()
+                    )?;
+                }
+            }
         }
 
         Ok(())
@@ -1,8 +1,14 @@
 use crate::analysis_error::AnalysisError;
 use crate::files::parsed_python_file::ParsedPythonFile;
-use crate::functions::{get_fixtures_mapping, get_functions, get_test_cases};
+use crate::functions::{
+    get_class_test_cases, get_fixtures_mapping, get_functions, get_partial_test_cases,
+    get_test_cases,
+};
+use crate::imports::ImportTable;
+use crate::suppressions::Suppressions;
+use rustpython_ast::{Stmt, Suite, TextSize};
+use rustpython_parser::{lexer::lex_starts_at, Parse, Tok};
 use rustpython_parser_core::Mode;
-use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct PythonFile {
@@ -10,30 +16,203 @@ pub struct PythonFile {
     pub filename: String,
 }
 
+/// Returns the offsets of the tokens that start a new top-level statement: the first token of each
+/// logical line outside of any indented block, except the clauses continuing a compound statement
+/// and the definitions following a decorator. Strings and brackets spanning several lines are
+/// single tokens or a single logical line, so their content never starts a statement. As the
+/// content may be invalid, a definition at column zero inside an unclosed bracket starts a new
+/// statement, from which lexing starts over, and lexing stops at the first error.
+fn get_top_level_offsets(content: &str) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut start = 0;
+
+    'lexing: loop {
+        let mut indentation = 0usize;
+        let mut depth = 0usize;
+        let mut at_line_start = true;
+        let mut after_decorator = false;
+
+        let tokens = lex_starts_at(&content[start..], Mode::Module, TextSize::new(start as u32));
+        for (token, range) in tokens.map_while(Result::ok) {
+            let offset = range.start().to_usize();
+            if depth > 0
+                && offset > start
+                && content[..offset].ends_with('\n')
+                && matches!(token, Tok::Def | Tok::Class | Tok::Async | Tok::At)
+            {
+                start = offset;
+                continue 'lexing;
+            }
+
+            match token {
+                Tok::Indent => indentation += 1,
+                Tok::Dedent => indentation = indentation.saturating_sub(1),
+                Tok::Newline => at_line_start = true,
+                Tok::NonLogicalNewline | Tok::Comment(_) => {}
+                _ => {
+                    match token {
+                        Tok::Lpar | Tok::Lsqb | Tok::Lbrace => depth += 1,
+                        Tok::Rpar | Tok::Rsqb | Tok::Rbrace => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    if !std::mem::take(&mut at_line_start)
+                        || indentation > 0
+                        || matches!(token, Tok::Else | Tok::Elif | Tok::Except | Tok::Finally)
+                    {
+                        continue;
+                    }
+
+                    if !after_decorator {
+                        offsets.push(offset);
+                    }
+                    after_decorator = token == Tok::At;
+                }
+            }
+        }
+
+        return offsets;
+    }
+}
+
+/// Parses the top-level statements of a file with a syntax error one by one, keeping the ones
+/// that parse, so that the definitions outside of the broken statement are still known.
+fn salvage_statements(content: &str, filename: &str) -> Vec<Stmt> {
+    let mut offsets = get_top_level_offsets(content);
+    offsets.push(content.len());
+
+    offsets
+        .windows(2)
+        .filter_map(|chunk| {
+            Suite::parse_starts_at(
+                &content[chunk[0]..chunk[1]],
+                filename,
+                TextSize::new(chunk[0] as u32),
+            )
+            .ok()
+        })
+        .flatten()
+        .collect()
+}
+
 impl PythonFile {
     pub fn parse(file: PythonFile) -> ParsedPythonFile {
         let parsed = rustpython_parser::parse(&file.content, Mode::Module, &file.filename);
 
-        return match parsed {
-            Err(_err) => ParsedPythonFile {
-                file,
-                errors: vec![AnalysisError::UnparsableFile],
-                fixtures: HashMap::new(),
-                test_cases: HashMap::new(),
-            },
+        match parsed {
+            Err(err) => {
+                let error = AnalysisError::UnparsableFile {
+                    message: err.error.to_string(),
+                    line: file.line_number(err.offset),
+                    column: file.column_number(err.offset),
+                };
+                let body = salvage_statements(&file.content, &file.filename);
+
+                PythonFile::from_body(file, body, vec![error])
+            }
             Ok(ast) => {
                 let ast = ast.as_module().unwrap().clone();
-                let functions = get_functions(&ast.body);
-                let fixtures = get_fixtures_mapping(&functions);
-                let test_cases = get_test_cases(&functions);
-
-                ParsedPythonFile {
-                    file,
-                    errors: Vec::new(),
-                    fixtures,
-                    test_cases,
-                }
+
+                PythonFile::from_body(file, ast.body, Vec::new())
             }
-        };
+        }
+    }
+
+    fn from_body(
+        file: PythonFile,
+        body: Vec<Stmt>,
+        errors: Vec<AnalysisError>,
+    ) -> ParsedPythonFile {
+        let functions = get_functions(&body);
+        let fixtures = get_fixtures_mapping(&functions);
+        let mut test_cases = get_test_cases(&functions);
+        test_cases.extend(get_class_test_cases(&body));
+        test_cases.extend(get_partial_test_cases(&body, &functions));
+
+        let imports = ImportTable::new(&body, &file.filename);
+        let suppressions = Suppressions::new(&file);
+
+        ParsedPythonFile {
+            file,
+            errors,
+            fixtures,
+            test_cases,
+            body,
+            imports,
+            suppressions,
+        }
+    }
+
+    /// Returns the one-indexed line number of a byte offset into the file's content.
+    pub fn line_number(&self, offset: TextSize) -> usize {
+        let offset = (offset.to_usize()).min(self.content.len());
+        bytecount::count(&self.content.as_bytes()[..offset], b'\n') + 1
+    }
+
+    /// Returns the one-indexed column, in characters, of a byte offset into the file's content.
+    pub fn column_number(&self, offset: TextSize) -> usize {
+        let offset = (offset.to_usize()).min(self.content.len());
+        let line_start = self.content[..offset].rfind('\n').map_or(0, |i| i + 1);
+        self.content[line_start..offset].chars().count() + 1
+    }
+
+    /// Returns the content of a one-indexed line, without its line ending.
+    pub fn line(&self, line_number: usize) -> Option<&str> {
+        self.content.lines().nth(line_number.checked_sub(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_ast::{Ranged, TextSize};
+
+    use super::{get_top_level_offsets, PythonFile};
+    use crate::analysis_error::AnalysisError;
+
+    fn python_file(content: &str) -> PythonFile {
+        PythonFile {
+            content: content.to_owned(),
+            filename: "test_file.py".to_owned(),
+        }
+    }
+
+    #[test]
+    fn assert_line_and_column_number() {
+        let file = python_file("x = 1\ny = 'é' + z\n");
+        let offset = TextSize::new(file.content.find('z').unwrap() as u32);
+
+        assert_eq!(file.line_number(offset), 2);
+        assert_eq!(file.column_number(offset), 11);
+        assert_eq!(file.line(2), Some("y = 'é' + z"));
+        assert_eq!(file.line(3), None);
+    }
+
+    #[test]
+    fn assert_get_top_level_offsets() {
+        let content = "import x\n\n@a\n@b\ndef f(\n    y,\n):\n    pass\n# comment\nif x:\n    pass\nelse:\n    pass\ndoc = \"\"\"\ndef g():\n\"\"\"\n";
+        let lines: Vec<&str> = get_top_level_offsets(content)
+            .into_iter()
+            .map(|offset| content[offset..].lines().next().unwrap())
+            .collect();
+
+        assert_eq!(lines, vec!["import x", "@a", "if x:", "doc = \"\"\""]);
+    }
+
+    #[test]
+    fn assert_parse_salvages_definitions() {
+        let parsed = PythonFile::parse(python_file(
+            "import pytest\n\n@pytest.fixture()\ndef user() -> str:\n    return ''\n\ndef broken(:\n    pass\n\ndef test_user(user: str):\n    pass\n",
+        ));
+
+        assert_eq!(
+            parsed.errors,
+            vec![AnalysisError::UnparsableFile {
+                message: "invalid syntax. Got unexpected token ':'".to_owned(),
+                line: 7,
+                column: 12,
+            }]
+        );
+        assert!(parsed.fixtures.contains_key("user"));
+        assert!(parsed.test_cases.contains_key("test_user"));
+        assert_eq!(parsed.file.line_number(parsed.body[2].start()), 10);
     }
 }
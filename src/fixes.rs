@@ -0,0 +1,596 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+};
+
+use rustpython_ast::{Expr, Ranged, Stmt, StmtFunctionDef, TextSize};
+use rustpython_parser::{lexer::lex_starts_at, Tok};
+use rustpython_parser_core::Mode;
+use rustpython_parser_vendored::text_size::TextRange;
+use similar::TextDiff;
+
+use crate::{
+    analysis_error::AnalysisError,
+    files::parsed_python_file::ParsedPythonFile,
+    fixture_index::{FixtureDefinition, FixtureIndex},
+    functions::format_annotation,
+    imports::ImportTable,
+    inference::{infer_fixture_type, InferredType},
+    location::{find_argument, find_function, Span},
+    render::requested_fixture,
+};
+
+/// Replaces a range of a file's content, the range being empty for insertions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    pub range: TextRange,
+    pub content: String,
+}
+
+impl Edit {
+    pub fn insertion(offset: TextSize, content: impl Into<String>) -> Self {
+        Edit::replacement(TextRange::empty(offset), content)
+    }
+
+    pub fn replacement(range: TextRange, content: impl Into<String>) -> Self {
+        Edit {
+            range,
+            content: content.into(),
+        }
+    }
+}
+
+/// Whether a fix keeps the meaning of the code. Unsafe fixes, such as replacing an annotation the
+/// user wrote, are only applied with `--unsafe-fixes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    Safe,
+    Unsafe,
+}
+
+/// Applies edits to `content`. Edits overlapping a previous one are dropped.
+pub fn apply_edits(content: &str, mut edits: Vec<Edit>) -> String {
+    edits.sort_by_key(|edit| edit.range.start());
+
+    let mut output = String::with_capacity(content.len());
+    let mut last = 0;
+    for edit in edits {
+        let start = usize::from(edit.range.start());
+        if start < last {
+            continue;
+        }
+        output.push_str(&content[last..start]);
+        output.push_str(&edit.content);
+        last = edit.range.end().into();
+    }
+    output.push_str(&content[last..]);
+
+    output
+}
+
+/// The imports needed by the names that fixes add to a file.
+#[derive(Debug, Clone, Default)]
+struct RequiredImports {
+    /// Modules imported with `import module`.
+    modules: BTreeSet<String>,
+    /// Names imported with `from module import name`, grouped by module.
+    names: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl RequiredImports {
+    /// The fully qualified name a name imported by an earlier fix refers to.
+    fn imported_name(&self, name: &str) -> Option<String> {
+        self.names
+            .iter()
+            .find(|(_, names)| names.contains(name))
+            .map(|(module, _)| format!("{}.{}", module, name))
+    }
+
+    /// Returns the name to write for a fully qualified name in a module with the given imports,
+    /// preferring `from module import name` unless `name` is already taken. Names defined in a
+    /// `conftest.py` file are not meant to be imported, so they give `None`.
+    fn require(&mut self, qualified: &str, imports: &ImportTable) -> Option<String> {
+        if let Some(binding) = imports.find_binding(qualified) {
+            return Some(binding);
+        }
+        let module = qualified
+            .rsplit_once('.')
+            .map_or(qualified, |(module, _)| module);
+        if module.rsplit('.').next() == Some("conftest") {
+            return None;
+        }
+
+        let name = match qualified.rsplit_once('.') {
+            Some((module, name))
+                if !imports.is_bound(name)
+                    && self
+                        .imported_name(name)
+                        .is_none_or(|imported| imported == qualified) =>
+            {
+                self.names
+                    .entry(module.to_owned())
+                    .or_default()
+                    .insert(name.to_owned());
+                name.to_owned()
+            }
+            Some((module, _)) => {
+                self.modules.insert(module.to_owned());
+                qualified.to_owned()
+            }
+            None => {
+                self.modules.insert(qualified.to_owned());
+                qualified.to_owned()
+            }
+        };
+        Some(name)
+    }
+
+    /// Returns the edits adding the imports to a file, extending an existing
+    /// `from module import ...` statement when there is one.
+    fn edits(&self, file: &ParsedPythonFile) -> Vec<Edit> {
+        let mut edits = Vec::new();
+        let mut statements: Vec<String> = self
+            .modules
+            .iter()
+            .map(|module| format!("import {}", module))
+            .collect();
+
+        for (module, names) in self.names.iter() {
+            let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+            let existing = file.body.iter().find_map(|stmt| match stmt {
+                Stmt::ImportFrom(import)
+                    if import.level.map_or(0, |l| l.to_usize()) == 0
+                        && import.module.as_deref() == Some(module.as_str())
+                        && import.names.iter().all(|a| a.name.as_str() != "*") =>
+                {
+                    import.names.last()
+                }
+                _ => None,
+            });
+
+            match existing {
+                Some(alias) => edits.push(Edit::insertion(
+                    alias.end(),
+                    format!(", {}", names.join(", ")),
+                )),
+                None => statements.push(format!("from {} import {}", module, names.join(", "))),
+            }
+        }
+
+        if !statements.is_empty() {
+            edits.push(insert_statements(file, &statements));
+        }
+        edits
+    }
+}
+
+fn is_docstring(stmt: &Stmt) -> bool {
+    stmt.as_expr_stmt()
+        .is_some_and(|expr| matches!(&*expr.value, Expr::Constant(c) if c.value.is_str()))
+}
+
+/// Inserts statements on new lines after the module's docstring and leading imports, or before its
+/// first statement.
+fn insert_statements(file: &ParsedPythonFile, statements: &[String]) -> Edit {
+    let content = &file.file.content;
+    let header = file
+        .body
+        .iter()
+        .take_while(|stmt| {
+            is_docstring(stmt) || stmt.is_import_stmt() || stmt.is_import_from_stmt()
+        })
+        .last();
+
+    match header {
+        Some(stmt) => {
+            let end = usize::from(stmt.end());
+            let line_end = content[end..]
+                .find('\n')
+                .map_or(content.len(), |position| end + position);
+            Edit::insertion(
+                TextSize::try_from(line_end).unwrap_or_default(),
+                format!("\n{}", statements.join("\n")),
+            )
+        }
+        None => Edit::insertion(
+            file.body
+                .first()
+                .map(|stmt| stmt.start())
+                .unwrap_or_default(),
+            format!("{}\n", statements.join("\n")),
+        ),
+    }
+}
+
+/// Writes the type of a fixture's value so that it can be used in `file`, recording the imports
+/// the annotation needs there.
+fn transfer_annotation(
+    fixture: &FixtureDefinition,
+    file: &ParsedPythonFile,
+    imports: &mut RequiredImports,
+) -> Option<String> {
+    let value = fixture.value_annotation()?;
+    let required = RefCell::new(Some(imports.clone()));
+
+    let annotation = format_annotation(&value, &|name| {
+        if !fixture.imports.is_bound(name) {
+            return name.to_owned();
+        }
+        let mut required = required.borrow_mut();
+        let written = required
+            .as_mut()
+            .and_then(|r| r.require(&fixture.imports.resolve(name), &file.imports));
+        if written.is_none() {
+            *required = None;
+        }
+        written.unwrap_or_default()
+    })?;
+
+    *imports = required.into_inner()?;
+    Some(annotation)
+}
+
+/// Returns the fix annotating an argument of the function `span` was recorded in, or re-annotating
+/// it when `annotated`, with the type of the fixture it receives.
+fn fix_argument_annotation(
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    argument_name: &str,
+    span: &Span,
+    annotated: bool,
+    imports: &mut RequiredImports,
+) -> Option<Edit> {
+    let func = find_function(file, span)?;
+    let arg = find_argument(func, argument_name)?;
+    if arg.def.annotation.is_some() != annotated {
+        return None;
+    }
+    let fixture = requested_fixture(file, index, fixtures, &func.name, argument_name)?;
+    let annotation = transfer_annotation(&fixture, file, imports)?;
+
+    match arg.def.annotation.as_deref() {
+        Some(current) => Some(Edit::replacement(current.range(), annotation)),
+        None => Some(Edit::insertion(arg.def.end(), format!(": {}", annotation))),
+    }
+}
+
+/// A file's content after its fixes are applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedFile {
+    pub filename: String,
+    pub original: String,
+    pub fixed: String,
+    /// The number of diagnostics the fixes address.
+    pub fixes: usize,
+}
+
+impl FixedFile {
+    /// Returns the changes as a unified diff.
+    pub fn diff(&self) -> String {
+        TextDiff::from_lines(&self.original, &self.fixed)
+            .unified_diff()
+            .header(&self.filename, &self.filename)
+            .to_string()
+    }
+
+    pub fn write(&self) -> Result<(), String> {
+        fs::write(&self.filename, &self.fixed)
+            .map_err(|err| format!("Unable to write {}: {}", self.filename, err))
+    }
+}
+
+/// Writes an inferred type in the fixture's module, recording the imports it needs there.
+fn write_inferred_type(
+    inferred: &InferredType,
+    file: &ParsedPythonFile,
+    imports: &mut RequiredImports,
+) -> Option<String> {
+    let mut write_all = |types: &[InferredType], separator: &str| -> Option<String> {
+        let written: Option<Vec<String>> = types
+            .iter()
+            .map(|t| write_inferred_type(t, file, imports))
+            .collect();
+        Some(written?.join(separator))
+    };
+
+    match inferred {
+        InferredType::Name(name) => Some(name.clone()),
+        InferredType::Generic(name, arguments) => {
+            Some(format!("{}[{}]", name, write_all(arguments, ", ")?))
+        }
+        InferredType::Union(types) => write_all(types, " | "),
+        InferredType::Iterator(value) => Some(format!(
+            "{}[{}]",
+            imports.require("typing.Iterator", &file.imports)?,
+            write_inferred_type(value, file, imports)?
+        )),
+        InferredType::Fixture(fixture) => transfer_annotation(fixture, file, imports),
+    }
+}
+
+/// Returns the offset right after the closing parenthesis of a function's parameters.
+fn get_parameters_end(func: &StmtFunctionDef, content: &str) -> Option<TextSize> {
+    let header = TextRange::new(func.start(), func.body.first()?.start());
+    let mut depth = 0;
+
+    for (token, range) in lex_starts_at(&content[header], Mode::Module, header.start()).flatten() {
+        match token {
+            Tok::Lpar => depth += 1,
+            Tok::Rpar if depth == 1 => return Some(range.end()),
+            Tok::Rpar => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the fix annotating a fixture with the return type inferred from its body.
+fn fix_fixture_return_type(
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    fixture_name: &str,
+    imports: &mut RequiredImports,
+) -> Option<Edit> {
+    let func = file.fixtures.get(fixture_name)?;
+    let inferred = infer_fixture_type(func, file, index, fixtures)?;
+
+    let mut required = imports.clone();
+    let annotation = write_inferred_type(&inferred, file, &mut required)?;
+    let end = get_parameters_end(func, &file.file.content)?;
+    *imports = required;
+
+    Some(Edit::insertion(end, format!(" -> {}", annotation)))
+}
+
+fn get_applicability(err: &AnalysisError) -> Option<Applicability> {
+    match err {
+        AnalysisError::MissingArgumentType { .. }
+        | AnalysisError::FixtureMissingReturnType { .. } => Some(Applicability::Safe),
+        AnalysisError::IncorrectArgumentType { .. } => Some(Applicability::Unsafe),
+        _ => None,
+    }
+}
+
+/// Fixes the diagnostics of a file that have a mechanical fix, such as a missing argument
+/// annotation whose fixture has a return annotation, or a missing return annotation that can be
+/// inferred from the fixture's body. Unsafe fixes are only applied with
+/// `unsafe_fixes`. The fixed diagnostics are removed from the file, which is otherwise left as
+/// parsed.
+pub fn fix_file(
+    file: &mut ParsedPythonFile,
+    index: &FixtureIndex,
+    unsafe_fixes: bool,
+) -> Option<FixedFile> {
+    let fixtures = index.visible_fixtures(file);
+    let mut imports = RequiredImports::default();
+    let mut edits: Vec<Edit> = Vec::new();
+    let mut fixed: Vec<bool> = Vec::new();
+
+    for err in file.errors.iter() {
+        let allowed = match get_applicability(err) {
+            Some(Applicability::Safe) => true,
+            Some(Applicability::Unsafe) => unsafe_fixes,
+            None => false,
+        };
+        let edit = match err {
+            AnalysisError::MissingArgumentType {
+                argument_name,
+                span,
+                ..
+            } if allowed => fix_argument_annotation(
+                file,
+                index,
+                &fixtures,
+                argument_name,
+                span,
+                false,
+                &mut imports,
+            ),
+            AnalysisError::IncorrectArgumentType {
+                argument_name,
+                span,
+                ..
+            } if allowed => fix_argument_annotation(
+                file,
+                index,
+                &fixtures,
+                argument_name,
+                span,
+                true,
+                &mut imports,
+            ),
+            AnalysisError::FixtureMissingReturnType { fixture_name, .. } if allowed => {
+                fix_fixture_return_type(file, index, &fixtures, fixture_name, &mut imports)
+            }
+            _ => None,
+        }
+        .filter(|edit| !edits.contains(edit));
+
+        fixed.push(edit.is_some());
+        edits.extend(edit);
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    let fixes = edits.len();
+    edits.extend(imports.edits(file));
+
+    let mut fixed = fixed.into_iter();
+    file.errors.retain(|_| !fixed.next().unwrap_or_default());
+
+    Some(FixedFile {
+        filename: file.file.filename.clone(),
+        fixed: apply_edits(&file.file.content, edits),
+        original: file.file.content.clone(),
+        fixes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rustpython_ast::TextSize;
+
+    use crate::{check_and_index_files, settings::Settings};
+
+    use super::{apply_edits, fix_file, Edit, FixedFile};
+
+    #[test]
+    fn assert_apply_edits() {
+        let edits = vec![
+            Edit::insertion(TextSize::new(10), ": int"),
+            Edit::insertion(TextSize::new(0), "import os\n"),
+        ];
+
+        assert_eq!(
+            apply_edits("def test(a): pass", edits),
+            "import os\ndef test(a: int): pass"
+        );
+    }
+
+    fn fix_example(filename: &str, unsafe_fixes: bool) -> (FixedFile, Vec<String>) {
+        let files = [PathBuf::from(filename)];
+        let (mut parsed, index) = check_and_index_files(files.iter(), &Settings::default());
+        let fixed = fix_file(&mut parsed[0], &index, unsafe_fixes).unwrap();
+        let mut remaining: Vec<String> = parsed[0].errors.iter().map(|err| err.symbol()).collect();
+        remaining.sort();
+
+        (fixed, remaining)
+    }
+
+    #[test]
+    fn assert_fix_missing_argument_types() {
+        let (fixed, remaining) = fix_example("./python-examples/fixes/test_fixes.py", false);
+
+        assert_eq!(fixed.fixes, 5);
+        assert_eq!(
+            fixed.fixed,
+            r#""""Tests with missing argument annotations."""
+import pytest
+from typing import Optional, Dict
+
+from models import Account
+import numpy
+from app.models import User
+from sqlite3 import Connection
+
+
+def test_user(user: User, account: Account):
+    assert user
+
+
+def test_settings(settings: Optional[Dict[str, int]], matrix: numpy.ndarray):
+    assert settings
+
+
+def test_connection(connection: Connection, session):
+    assert connection
+
+
+def test_wrong_types(user: int, connection: str):
+    assert user
+"#
+        );
+        assert_eq!(
+            remaining,
+            vec![
+                "test_connection::session",
+                "test_wrong_types::connection",
+                "test_wrong_types::user"
+            ]
+        );
+    }
+
+    #[test]
+    fn assert_fix_incorrect_argument_types() {
+        let (fixed, remaining) = fix_example("./python-examples/fixes/test_fixes.py", true);
+
+        assert_eq!(fixed.fixes, 7);
+        assert!(fixed
+            .fixed
+            .contains("def test_wrong_types(user: User, connection: Connection):"));
+        assert_eq!(remaining, vec!["test_connection::session"]);
+    }
+
+    #[test]
+    fn assert_fix_functions_sharing_their_name() {
+        let (fixed, remaining) = fix_example("./python-examples/fixes/test_same_name.py", false);
+
+        assert_eq!(fixed.fixes, 1);
+        assert_eq!(
+            fixed.fixed,
+            r#""""Test classes with methods sharing their name."""
+from app.models import User
+
+
+class TestFirst:
+    def test_user(self, user: int):
+        assert user
+
+
+class TestSecond:
+    def test_user(self, user: User):
+        assert user
+"#
+        );
+        assert_eq!(remaining, vec!["test_user::user"]);
+    }
+
+    #[test]
+    fn assert_fix_fixture_return_types() {
+        let (fixed, remaining) = fix_example("./python-examples/fixes/test_inferred.py", false);
+
+        assert_eq!(fixed.fixes, 6);
+        assert_eq!(
+            fixed.fixed,
+            r#"import pytest
+
+from app.models import User
+from typing import Iterator
+
+
+@pytest.fixture()
+def name() -> str:
+    return "alice"
+
+
+@pytest.fixture(params=[1, 2])
+def number(request) -> int:
+    return request.param
+
+
+@pytest.fixture()
+def admin(user: User) -> User:
+    return user
+
+
+@pytest.fixture()
+def created_user() -> Iterator[User]:
+    user = User()
+    yield user
+
+
+@pytest.fixture()
+def scores(
+    name: str,
+) -> dict[str, list[float]]:
+    return {name: [1.5]}
+
+
+@pytest.fixture()
+def unknown():
+    return compute()
+
+
+def test_users(name: str, number: int, admin: User, created_user: User, unknown: int):
+    assert scores
+"#
+        );
+        assert_eq!(remaining, vec!["number::request", "unknown"]);
+    }
+}
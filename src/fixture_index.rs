@@ -0,0 +1,410 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use rustpython_ast::{Expr, Ranged, StmtFunctionDef};
+use serde::Serialize;
+
+use crate::{
+    files::parsed_python_file::ParsedPythonFile,
+    functions::{get_annotation, get_yield_type, is_generator},
+    imports::{ImportTable, ResolvedAnnotation},
+    inference::{infer_fixture_type, normalize_builtin_aliases},
+    location::SourceLine,
+    nodes::{get_string_literal, FixtureUtil},
+    plugins::{get_fixture_imports, get_pytest_plugins, resolve_module},
+};
+
+pub const CONFTEST_FILENAME: &str = "conftest.py";
+/// The scope of fixtures declared without one.
+pub const DEFAULT_SCOPE: &str = "function";
+
+#[derive(Debug, Clone)]
+pub struct FixtureDefinition {
+    pub function: StmtFunctionDef,
+    pub filename: String,
+    pub line: usize,
+    /// Imports of the defining module, used to resolve the fixture's annotations.
+    pub imports: ImportTable,
+    /// Span of the return annotation, for rendering diagnostics in other files.
+    pub returns_source: Option<SourceLine>,
+}
+
+impl FixtureDefinition {
+    pub fn new(function: &StmtFunctionDef, file: &ParsedPythonFile) -> Self {
+        FixtureDefinition {
+            function: function.clone(),
+            filename: file.file.filename.clone(),
+            line: file.file.line_number(function.start()),
+            imports: file.imports.clone(),
+            returns_source: function
+                .returns
+                .as_ref()
+                .map(|returns| SourceLine::new(&file.file, returns.range())),
+        }
+    }
+
+    /// The annotation of the value requesting functions receive, which is the yielded type for a
+    /// generator fixture annotated with `Iterator[T]` or `Generator[T, ...]`.
+    pub fn value_annotation(&self) -> Option<Expr> {
+        let returns = self.function.returns.as_deref()?;
+
+        if is_generator(&self.function) {
+            if let Some(yield_type) = get_yield_type(returns) {
+                return Some(yield_type);
+            }
+        }
+        Some(returns.clone())
+    }
+
+    pub fn return_annotation(&self) -> Option<ResolvedAnnotation> {
+        self.imports.resolve_annotation(&self.value_annotation()?)
+    }
+
+    /// The scope given to the fixture decorator, as written when it is not a string literal,
+    /// e.g. a callable determining the scope dynamically.
+    pub fn scope(&self) -> String {
+        self.function
+            .fixture_keyword("scope")
+            .and_then(|scope| get_string_literal(scope).or_else(|| get_annotation(scope)))
+            .unwrap_or_else(|| DEFAULT_SCOPE.to_owned())
+    }
+
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.filename, self.line)
+    }
+}
+
+/// Where a fixture visible from a file is defined, by decreasing precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum FixtureSource {
+    /// The file itself.
+    Module,
+    /// Another module the fixture is imported from.
+    Imported,
+    /// A `conftest.py` file, the nearest one being layer 0.
+    Conftest { layer: usize },
+    /// A module registered through `pytest_plugins`.
+    Plugin,
+}
+
+impl fmt::Display for FixtureSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixtureSource::Module => write!(f, "module"),
+            FixtureSource::Imported => write!(f, "imported"),
+            FixtureSource::Conftest { layer } => write!(f, "conftest (layer {})", layer),
+            FixtureSource::Plugin => write!(f, "plugin"),
+        }
+    }
+}
+
+/// The fixtures visible from each file besides its own: the ones defined in `conftest.py` files,
+/// grouped by the directory they apply to, the ones imported into a module and the ones registered
+/// through `pytest_plugins`.
+#[derive(Debug, Default)]
+pub struct FixtureIndex {
+    conftests: HashMap<PathBuf, HashMap<String, FixtureDefinition>>,
+    imported: HashMap<PathBuf, HashMap<String, FixtureDefinition>>,
+    plugins: HashMap<String, FixtureDefinition>,
+    /// The value types inferred for the fixtures without a return annotation, by file and
+    /// function name.
+    inferred: HashMap<(PathBuf, String), ResolvedAnnotation>,
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    // The parent of a bare filename such as `conftest.py` is the empty path.
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub fn is_conftest(filename: &str) -> bool {
+    Path::new(filename)
+        .file_name()
+        .is_some_and(|name| name == CONFTEST_FILENAME)
+}
+
+impl FixtureIndex {
+    pub fn new<'a, I>(files: I, source_roots: &[PathBuf]) -> Self
+    where
+        I: Iterator<Item = &'a ParsedPythonFile>,
+    {
+        let files: Vec<&ParsedPythonFile> = files.collect();
+        let modules: HashMap<PathBuf, &ParsedPythonFile> = files
+            .iter()
+            .map(|f| (normalize(Path::new(&f.file.filename)), *f))
+            .collect();
+        let resolve = |module: &str, level: usize, file: &ParsedPythonFile| {
+            resolve_module(module, level, Path::new(&file.file.filename), source_roots)
+                .and_then(|path| modules.get(&normalize(&path)).copied())
+        };
+
+        let mut imported = HashMap::new();
+        for file in files.iter() {
+            let mut fixtures = HashMap::new();
+
+            for import in get_fixture_imports(&file.body) {
+                let Some(module) = resolve(&import.module, import.level, file) else {
+                    continue;
+                };
+
+                match import.names {
+                    None => {
+                        fixtures.extend(module.fixtures.iter().map(|(name, func)| {
+                            (name.clone(), FixtureDefinition::new(func, module))
+                        }))
+                    }
+                    Some(names) => {
+                        fixtures.extend(names.into_iter().filter_map(|(name, alias)| {
+                            let func = module.fixtures.get(&name)?;
+                            Some((alias, FixtureDefinition::new(func, module)))
+                        }))
+                    }
+                }
+            }
+
+            if !fixtures.is_empty() {
+                imported.insert(normalize(Path::new(&file.file.filename)), fixtures);
+            }
+        }
+
+        let mut plugins = HashMap::new();
+        for file in files.iter() {
+            for plugin in get_pytest_plugins(&file.body) {
+                let Some(module) = resolve(&plugin, 0, file) else {
+                    continue;
+                };
+                let path = normalize(Path::new(&module.file.filename));
+
+                if let Some(fixtures) = imported.get(&path) {
+                    plugins.extend(fixtures.clone());
+                }
+                plugins.extend(
+                    module
+                        .fixtures
+                        .iter()
+                        .map(|(name, func)| (name.clone(), FixtureDefinition::new(func, module))),
+                );
+            }
+        }
+
+        let mut conftests = HashMap::new();
+        for file in files.iter().filter(|f| is_conftest(&f.file.filename)) {
+            let directory = Path::new(&file.file.filename)
+                .parent()
+                .map(normalize)
+                .unwrap_or_default();
+            let fixtures = file
+                .fixtures
+                .iter()
+                .map(|(name, func)| (name.clone(), FixtureDefinition::new(func, file)))
+                .collect();
+
+            conftests.insert(directory, fixtures);
+        }
+
+        FixtureIndex {
+            conftests,
+            imported,
+            plugins,
+            inferred: HashMap::new(),
+        }
+    }
+
+    /// Infers the value types of the fixtures of `files` that have no return annotation from their
+    /// bodies, for [`FixtureIndex::inferred_type`].
+    pub fn infer_types<'a, I>(&mut self, files: I)
+    where
+        I: Iterator<Item = &'a ParsedPythonFile>,
+    {
+        let mut inferred = HashMap::new();
+
+        for file in files {
+            let fixtures = self.visible_fixtures(file);
+            let path = normalize(Path::new(&file.file.filename));
+
+            for func in file.fixtures.values().filter(|f| f.returns.is_none()) {
+                let Some(value_type) = infer_fixture_type(func, file, self, &fixtures) else {
+                    continue;
+                };
+                let value_type = value_type.value_type();
+                let Some(qualified) = value_type.qualify(&file.imports) else {
+                    continue;
+                };
+
+                inferred.insert(
+                    (path.clone(), func.name.to_string()),
+                    ResolvedAnnotation {
+                        written: value_type.to_string(),
+                        qualified: normalize_builtin_aliases(&qualified),
+                    },
+                );
+            }
+        }
+
+        self.inferred = inferred;
+    }
+
+    /// Returns the value type inferred for a fixture without a return annotation, when types were
+    /// inferred with [`FixtureIndex::infer_types`].
+    pub fn inferred_type(&self, fixture: &FixtureDefinition) -> Option<&ResolvedAnnotation> {
+        self.inferred.get(&(
+            normalize(Path::new(&fixture.filename)),
+            fixture.function.name.to_string(),
+        ))
+    }
+
+    /// Returns the conftest layers that apply to `filename`, nearest first. A conftest file does
+    /// not see itself as one of its layers.
+    pub fn conftest_layers(&self, filename: &str) -> Vec<&HashMap<String, FixtureDefinition>> {
+        let path = normalize(Path::new(filename));
+        let skip = usize::from(is_conftest(filename));
+
+        path.ancestors()
+            .skip(1 + skip)
+            .filter_map(|directory| self.conftests.get(directory))
+            .collect()
+    }
+
+    /// Returns the fixtures visible from `file`, by decreasing precedence: its own and imported
+    /// fixtures, the ones of the nearest conftest files, then the ones of plugins.
+    pub fn visible_fixtures(&self, file: &ParsedPythonFile) -> HashMap<String, FixtureDefinition> {
+        self.visible_fixture_sources(file)
+            .into_iter()
+            .map(|(name, (_, fixture))| (name, fixture))
+            .collect()
+    }
+
+    /// Returns the fixtures visible from `file` like [`FixtureIndex::visible_fixtures`], along with
+    /// where each of them comes from.
+    pub fn visible_fixture_sources(
+        &self,
+        file: &ParsedPythonFile,
+    ) -> HashMap<String, (FixtureSource, FixtureDefinition)> {
+        self.fixture_definitions(file)
+            .into_iter()
+            .filter_map(|(name, definitions)| Some((name, definitions.into_iter().next()?)))
+            .collect()
+    }
+
+    /// Returns every definition of the fixtures visible from `file` by decreasing precedence,
+    /// including the overridden ones a fixture receives when it requests its own name.
+    pub fn fixture_definitions(
+        &self,
+        file: &ParsedPythonFile,
+    ) -> HashMap<String, Vec<(FixtureSource, FixtureDefinition)>> {
+        let mut definitions: HashMap<String, Vec<(FixtureSource, FixtureDefinition)>> =
+            HashMap::new();
+        let mut add = |source: FixtureSource, name: &String, fixture: FixtureDefinition| {
+            definitions
+                .entry(name.clone())
+                .or_default()
+                .push((source, fixture))
+        };
+
+        for (name, func) in file.fixtures.iter() {
+            add(
+                FixtureSource::Module,
+                name,
+                FixtureDefinition::new(func, file),
+            );
+        }
+        if let Some(imported) = self
+            .imported
+            .get(&normalize(Path::new(&file.file.filename)))
+        {
+            for (name, fixture) in imported.iter() {
+                add(FixtureSource::Imported, name, fixture.clone());
+            }
+        }
+        for (layer, fixtures) in self
+            .conftest_layers(&file.file.filename)
+            .into_iter()
+            .enumerate()
+        {
+            for (name, fixture) in fixtures.iter() {
+                add(FixtureSource::Conftest { layer }, name, fixture.clone());
+            }
+        }
+        for (name, fixture) in self.plugins.iter() {
+            add(FixtureSource::Plugin, name, fixture.clone());
+        }
+
+        definitions
+    }
+
+    /// Returns the definition a fixture of `file` named `name` overrides, if any.
+    pub fn overridden_fixture(
+        &self,
+        file: &ParsedPythonFile,
+        name: &str,
+    ) -> Option<&FixtureDefinition> {
+        self.conftest_layers(&file.file.filename)
+            .into_iter()
+            .find_map(|layer| layer.get(name))
+            .or_else(|| self.plugins.get(name))
+    }
+}
+
+/// Returns the `conftest.py` files of the directories containing `files` and of their ancestors,
+/// excluding the ones already part of `files`.
+pub fn get_conftest_files(files: &[PathBuf]) -> Vec<PathBuf> {
+    let provided: Vec<PathBuf> = files.iter().map(|f| normalize(f)).collect();
+    let mut conftests: Vec<PathBuf> = Vec::new();
+
+    for file in files {
+        for directory in file.ancestors().skip(1) {
+            let conftest = directory.join(CONFTEST_FILENAME);
+            let normalized = normalize(&conftest);
+
+            if conftest.is_file()
+                && !provided.contains(&normalized)
+                && !conftests.iter().any(|c| normalize(c) == normalized)
+            {
+                conftests.push(conftest);
+            }
+        }
+    }
+
+    conftests
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, path::PathBuf};
+
+    use tempfile::tempdir;
+
+    use super::{get_conftest_files, is_conftest};
+
+    #[test]
+    fn assert_is_conftest() {
+        assert!(is_conftest("tests/conftest.py"));
+        assert!(!is_conftest("tests/test_conftest.py"));
+    }
+
+    #[test]
+    fn assert_get_conftest_files() -> anyhow::Result<()> {
+        let base_dir: PathBuf = tempdir()?.keep();
+        std::fs::create_dir(base_dir.join("sub"))?;
+        File::create(base_dir.join("conftest.py"))?;
+        File::create(base_dir.join("sub").join("conftest.py"))?;
+        File::create(base_dir.join("sub").join("test_file.py"))?;
+
+        let conftests = get_conftest_files(&[
+            base_dir.join("sub").join("test_file.py"),
+            base_dir.join("sub").join("conftest.py"),
+        ]);
+
+        assert_eq!(conftests, vec![base_dir.join("conftest.py")]);
+
+        Ok(())
+    }
+}
@@ -1,8 +1,17 @@
 use std::collections::HashMap;
 
-use rustpython_ast::{ArgWithDefault, Expr, ExprSubscript, Stmt, StmtFunctionDef};
+use rustpython_ast::{
+    ArgWithDefault, Constant, Expr, ExprConstant, ExprLambda, ExprSubscript, ExprYield,
+    ExprYieldFrom, Ranged, Stmt, StmtAsyncFunctionDef, StmtClassDef, StmtFunctionDef, TextSize,
+    Visitor,
+};
+use rustpython_parser::Parse;
 
-use crate::nodes::FunctionUtil;
+use crate::{
+    hypothesis::get_given_argument_names,
+    mocks::{get_mock_keyword_args, num_mock_patch_args},
+    nodes::FunctionUtil,
+};
 
 pub fn get_functions(stmts: &[Stmt]) -> Vec<StmtFunctionDef> {
     stmts
@@ -27,6 +36,23 @@ pub fn get_fixtures_mapping(functions: &[StmtFunctionDef]) -> HashMap<String, St
     mapping
 }
 
+/// Returns the pairs of fixtures sharing a name, where the second definition silently replaces the
+/// first one in [`get_fixtures_mapping`].
+pub fn get_duplicate_fixtures(
+    functions: &[StmtFunctionDef],
+) -> Vec<(&StmtFunctionDef, &StmtFunctionDef)> {
+    let mut seen: HashMap<String, &StmtFunctionDef> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for f in functions.iter().filter(|f| f.is_pytest_fixture()) {
+        if let Some(previous) = seen.insert(f.name.to_string(), f) {
+            duplicates.push((previous, f));
+        }
+    }
+
+    duplicates
+}
+
 pub fn get_test_cases(functions: &[StmtFunctionDef]) -> HashMap<String, StmtFunctionDef> {
     let mut mapping = HashMap::new();
 
@@ -40,35 +66,290 @@ pub fn get_test_cases(functions: &[StmtFunctionDef]) -> HashMap<String, StmtFunc
     mapping
 }
 
-fn unfold_subscript(expr: &ExprSubscript) -> Option<String> {
-    let value = expr.value.as_name_expr()?.id.to_string();
+/// Returns the test methods of `Test*` classes, with the bound `self`/`cls` argument removed unless
+/// the method is a `staticmethod`.
+pub fn get_class_test_cases(stmts: &[Stmt]) -> HashMap<String, StmtFunctionDef> {
+    let mut mapping = HashMap::new();
+
+    for stmt in stmts {
+        let Stmt::ClassDef(class) = stmt else {
+            continue;
+        };
+        if !class.name.starts_with("Test") {
+            continue;
+        }
+
+        for f in get_functions(&class.body) {
+            let name = f.name.to_string();
+            if f.is_pytest_fixture() || !name.starts_with("test_") {
+                continue;
+            }
 
-    match *expr.slice {
-        Expr::Name(ref expr) => Some(format!("{}[{}]", value, expr.id)),
-        Expr::Subscript(ref expr) => {
-            Some(format!("{}[{}]", value, unfold_subscript(expr).unwrap()))
+            let is_staticmethod = f
+                .decorator_list
+                .iter()
+                .any(|d| matches!(d.as_name_expr(), Some(n) if n.id.as_str() == "staticmethod"));
+            let f = if is_staticmethod {
+                f
+            } else {
+                drop_positional_arguments(&f, 1)
+            };
+
+            mapping.insert(format!("{}::{}", class.name, name), f);
         }
-        Expr::Tuple(ref expr) => {
-            let tuple: Vec<String> = expr
+    }
+
+    mapping
+}
+
+/// Returns the tests defined as `test_x = functools.partial(func, ...)`. The arguments bound by the
+/// partial are removed from the resulting function.
+pub fn get_partial_test_cases(
+    stmts: &[Stmt],
+    functions: &[StmtFunctionDef],
+) -> HashMap<String, StmtFunctionDef> {
+    let mut mapping = HashMap::new();
+
+    for stmt in stmts {
+        let Stmt::Assign(assign) = stmt else {
+            continue;
+        };
+        let (Some(target), Some(call)) = (
+            assign.targets.first().and_then(|t| t.as_name_expr()),
+            assign.value.as_call_expr(),
+        ) else {
+            continue;
+        };
+        let is_partial = match &*call.func {
+            Expr::Name(name) => name.id.as_str() == "partial",
+            Expr::Attribute(attribute) => attribute.attr.as_str() == "partial",
+            _ => false,
+        };
+        let name = target.id.to_string();
+        if !is_partial || !name.starts_with("test_") {
+            continue;
+        }
+
+        let Some(wrapped) = call
+            .args
+            .first()
+            .and_then(|a| a.as_name_expr())
+            .and_then(|wrapped| functions.iter().find(|f| f.name == wrapped.id))
+        else {
+            continue;
+        };
+
+        let mut f = drop_positional_arguments(wrapped, call.args.len() - 1);
+        let bound: Vec<&str> = call
+            .keywords
+            .iter()
+            .filter_map(|k| k.arg.as_ref().map(|a| a.as_str()))
+            .collect();
+        f.args.args.retain(|a| !bound.contains(&a.def.arg.as_str()));
+        f.args
+            .kwonlyargs
+            .retain(|a| !bound.contains(&a.def.arg.as_str()));
+        f.name = target.id.clone();
+
+        mapping.insert(name, f);
+    }
+
+    mapping
+}
+
+fn drop_positional_arguments(func: &StmtFunctionDef, count: usize) -> StmtFunctionDef {
+    let mut func = func.clone();
+    let from_posonly = count.min(func.args.posonlyargs.len());
+    func.args.posonlyargs.drain(..from_posonly);
+    let from_args = (count - from_posonly).min(func.args.args.len());
+    func.args.args.drain(..from_args);
+    func
+}
+
+/// Returns the arguments pytest provides fixtures for, following pytest's `getfuncargnames`:
+/// positional-or-keyword and keyword-only arguments without a default value, minus the leading
+/// arguments injected by `mock.patch` decorators and the keyword arguments injected by
+/// `patch.multiple`. Arguments bound by hypothesis's `@given` are not fixtures either, and neither
+/// are positional-only arguments, `*args` and `**kwargs`.
+pub fn get_fixture_arguments(func: &StmtFunctionDef) -> Vec<&ArgWithDefault> {
+    let mock_keywords = get_mock_keyword_args(&func.decorator_list);
+    let given = get_given_argument_names(func);
+
+    func.args
+        .args
+        .iter()
+        .chain(func.args.kwonlyargs.iter())
+        .filter(|arg| arg.default.is_none())
+        .skip(num_mock_patch_args(&func.decorator_list))
+        .filter(|arg| !mock_keywords.iter().any(|k| k == arg.def.arg.as_str()))
+        .filter(|arg| !given.iter().any(|g| g == arg.def.arg.as_str()))
+        .collect()
+}
+
+fn format_elements(elements: &[Expr], resolve: &dyn Fn(&str) -> String) -> Option<String> {
+    let elements: Option<Vec<String>> = elements
+        .iter()
+        .map(|e| format_annotation(e, resolve))
+        .collect();
+
+    Some(elements?.join(", "))
+}
+
+/// Formats the values of `Literal[...]`, which are kept as written instead of being parsed as
+/// forward references.
+fn format_literal_values(expr: &Expr, resolve: &dyn Fn(&str) -> String) -> Option<String> {
+    match expr {
+        Expr::Tuple(tuple) => {
+            let values: Option<Vec<String>> = tuple
                 .elts
                 .iter()
-                .map(|v| v.as_name_expr().unwrap().id.to_string())
+                .map(|e| format_literal_values(e, resolve))
                 .collect();
-
-            Some(format!("{}[{}]", value, tuple.join(", ")))
+            Some(values?.join(", "))
         }
-        _ => panic!("Incorrect type {:#?}", expr.slice),
+        Expr::Constant(constant) => Some(constant.value.to_string()),
+        Expr::UnaryOp(op) if op.op.is_u_sub() => {
+            Some(format!("-{}", format_literal_values(&op.operand, resolve)?))
+        }
+        _ => format_annotation(expr, resolve),
     }
 }
 
-pub fn get_annotation(expr: &Expr) -> Option<String> {
+fn unfold_subscript(expr: &ExprSubscript, resolve: &dyn Fn(&str) -> String) -> Option<String> {
+    let value = format_annotation(&expr.value, resolve)?;
+    let name = value.rsplit('.').next().unwrap_or_default();
+
+    let slice = match (name, &*expr.slice) {
+        ("Literal", slice) => format_literal_values(slice, resolve)?,
+        // The metadata of `Annotated[T, ...]` does not change the type of the value.
+        ("Annotated", Expr::Tuple(tuple)) => {
+            return format_annotation(tuple.elts.first()?, resolve)
+        }
+        (_, Expr::Tuple(tuple)) => format_elements(&tuple.elts, resolve)?,
+        (_, slice) => format_annotation(slice, resolve)?,
+    };
+
+    Some(format!("{}[{}]", value, slice))
+}
+
+/// Formats an annotation, passing every name through `resolve`. Attribute chains such as
+/// `pathlib.Path` only have their first name resolved. Returns `None` for expressions that are not
+/// valid annotations.
+pub fn format_annotation(expr: &Expr, resolve: &dyn Fn(&str) -> String) -> Option<String> {
     match expr {
-        Expr::Name(v) => Some(v.id.to_string()),
-        Expr::Subscript(v) => unfold_subscript(v),
+        Expr::Name(v) => Some(resolve(&v.id)),
+        Expr::Attribute(v) => Some(format!(
+            "{}.{}",
+            format_annotation(&v.value, resolve)?,
+            v.attr
+        )),
+        Expr::Subscript(v) => unfold_subscript(v, resolve),
+        // The parameter list of `Callable[[int, str], bool]`.
+        Expr::List(v) => Some(format!("[{}]", format_elements(&v.elts, resolve)?)),
+        Expr::BinOp(v) if v.op.is_bit_or() => Some(format!(
+            "{} | {}",
+            format_annotation(&v.left, resolve)?,
+            format_annotation(&v.right, resolve)?
+        )),
+        Expr::Constant(v) => match v.value {
+            Constant::None => Some("None".to_owned()),
+            Constant::Ellipsis => Some("...".to_owned()),
+            _ => format_annotation(&parse_string_annotation(v)?, resolve),
+        },
         _ => None,
     }
 }
 
+/// Parses a quoted annotation such as `"User"`, `r"User"` or `"""User"""` as a Python expression,
+/// the way `typing.get_type_hints` evaluates forward references. The parsed expression keeps its
+/// offsets in the original source: the literal is longer than its value by its prefix and its
+/// quotes, one or three on each side, which tells where the value starts. Escape sequences and
+/// implicitly concatenated literals, which annotations have no use for, would shift the offsets.
+pub fn parse_string_annotation(constant: &ExprConstant) -> Option<Expr> {
+    let Constant::Str(value) = &constant.value else {
+        return None;
+    };
+    let delimiters = constant
+        .range()
+        .len()
+        .to_usize()
+        .saturating_sub(value.len());
+    // Up to two prefix characters such as `r` or `u`, then one or three quotes.
+    let content_start = match delimiters {
+        6.. => delimiters - 3,
+        2.. => delimiters - 1,
+        _ => 0,
+    };
+
+    Expr::parse_starts_at(
+        value,
+        "<annotation>",
+        constant.start() + TextSize::new(content_start as u32),
+    )
+    .ok()
+}
+
+#[derive(Default)]
+struct YieldVisitor {
+    found: bool,
+}
+
+impl Visitor for YieldVisitor {
+    fn visit_expr_yield(&mut self, _node: ExprYield) {
+        self.found = true;
+    }
+
+    fn visit_expr_yield_from(&mut self, _node: ExprYieldFrom) {
+        self.found = true;
+    }
+
+    // Nested scopes do not make the enclosing function a generator.
+    fn visit_stmt_function_def(&mut self, _node: StmtFunctionDef) {}
+
+    fn visit_stmt_async_function_def(&mut self, _node: StmtAsyncFunctionDef) {}
+
+    fn visit_stmt_class_def(&mut self, _node: StmtClassDef) {}
+
+    fn visit_expr_lambda(&mut self, _node: ExprLambda) {}
+}
+
+/// Whether a function's body contains a `yield`, making it a generator.
+pub fn is_generator(func: &StmtFunctionDef) -> bool {
+    let mut visitor = YieldVisitor::default();
+    for stmt in func.body.iter() {
+        visitor.visit_stmt(stmt.clone());
+    }
+    visitor.found
+}
+
+/// Returns the type of the values yielded by a generator annotated with `annotation`, e.g. `User`
+/// for `Iterator[User]` or `Generator[User, None, None]`.
+pub fn get_yield_type(annotation: &Expr) -> Option<Expr> {
+    let subscript = match annotation {
+        Expr::Subscript(subscript) => subscript.clone(),
+        Expr::Constant(constant) => parse_string_annotation(constant)?.subscript_expr()?,
+        _ => return None,
+    };
+
+    let name = get_annotation(&subscript.value)?;
+    match name.rsplit('.').next() {
+        Some(
+            "Generator" | "Iterator" | "Iterable" | "AsyncGenerator" | "AsyncIterator"
+            | "AsyncIterable",
+        ) => {}
+        _ => return None,
+    }
+
+    match *subscript.slice {
+        Expr::Tuple(tuple) => tuple.elts.into_iter().next(),
+        slice => Some(slice),
+    }
+}
+
+pub fn get_annotation(expr: &Expr) -> Option<String> {
+    format_annotation(expr, &|name| name.to_owned())
+}
+
 pub fn get_argument_annotation(arg: &ArgWithDefault) -> Option<String> {
     get_annotation(arg.def.annotation.as_deref()?)
 }
@@ -85,8 +366,19 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::functions::{
-        get_fixtures_mapping, get_functions, get_return_annotation, get_test_cases,
+        get_annotation, get_class_test_cases, get_duplicate_fixtures, get_fixture_arguments,
+        get_fixtures_mapping, get_functions, get_partial_test_cases, get_return_annotation,
+        get_test_cases,
     };
+    use rustpython_ast::{Expr, Ranged, Suite};
+    use rustpython_parser::Parse;
+
+    fn get_argument_names(func: &StmtFunctionDef) -> Vec<String> {
+        get_fixture_arguments(func)
+            .iter()
+            .map(|a| a.def.arg.to_string())
+            .collect()
+    }
 
     #[test]
     fn assert_get_functions_empty() {
@@ -113,6 +405,17 @@ mod tests {
         assert_eq!(get_fixtures_mapping(&functions), expected);
     }
 
+    #[test]
+    fn assert_get_duplicate_fixtures() {
+        let mut functions = get_functions(&create_functions());
+        assert!(get_duplicate_fixtures(&functions).is_empty());
+
+        functions.push(functions[1].clone());
+        let duplicates = get_duplicate_fixtures(&functions);
+
+        assert_eq!(duplicates, vec![(&functions[1], &functions[2])]);
+    }
+
     #[test]
     fn assert_get_test_cases() {
         let functions = get_functions(&create_functions());
@@ -134,4 +437,138 @@ mod tests {
             expected
         );
     }
+
+    #[test]
+    fn assert_get_string_annotation() {
+        let annotation = |source: &str| get_annotation(&Expr::parse(source, "<test>").unwrap());
+
+        assert_eq!(annotation("'User'"), Some("User".to_owned()));
+        assert_eq!(
+            annotation("'Dict[str, List[User]]'"),
+            Some("Dict[str, List[User]]".to_owned())
+        );
+        assert_eq!(annotation("List['User']"), Some("List[User]".to_owned()));
+        assert_eq!(annotation("'not an expression('"), None);
+        assert_eq!(annotation("1"), None);
+    }
+
+    #[test]
+    fn assert_get_unusual_annotations() {
+        let annotation = |source: &str| get_annotation(&Expr::parse(source, "<test>").unwrap());
+
+        assert_eq!(
+            annotation("Literal['a', 1, -2, None]"),
+            Some("Literal['a', 1, -2, None]".to_owned())
+        );
+        assert_eq!(
+            annotation("Callable[[int, 'User'], str]"),
+            Some("Callable[[int, User], str]".to_owned())
+        );
+        assert_eq!(
+            annotation("Tuple[int, ...]"),
+            Some("Tuple[int, ...]".to_owned())
+        );
+        assert_eq!(
+            annotation("Dict[str, 'Foo']"),
+            Some("Dict[str, Foo]".to_owned())
+        );
+        assert_eq!(annotation("Annotated[int, Gt(0)]"), Some("int".to_owned()));
+        assert_eq!(
+            annotation("Optional[List[None]]"),
+            Some("Optional[List[None]]".to_owned())
+        );
+        assert_eq!(annotation("int | None"), Some("int | None".to_owned()));
+        assert_eq!(annotation("List[make_type()]"), None);
+        assert_eq!(annotation("lambda: int"), None);
+    }
+
+    #[test]
+    fn assert_parse_string_annotation_offsets() {
+        for source in [
+            "x: 'List[User]'",
+            "x: r'List[User]'",
+            r#"x: """List[User]""""#,
+            "x: u'''List[User]'''",
+        ] {
+            let stmts = Suite::parse(source, "<test>").unwrap();
+            let annotation = stmts[0].as_ann_assign_stmt().unwrap().annotation.as_ref();
+            let parsed = super::parse_string_annotation(annotation.as_constant_expr().unwrap());
+
+            assert_eq!(&source[parsed.unwrap().range()], "List[User]", "{}", source);
+        }
+    }
+
+    #[test]
+    fn assert_is_generator() {
+        let stmts = Suite::parse(
+            "def a():\n    yield 1\ndef b():\n    def inner():\n        yield 1\n    return inner\ndef c():\n    x = yield from range(3)\n",
+            "<test>",
+        )
+        .unwrap();
+        let functions = get_functions(&stmts);
+
+        assert!(super::is_generator(&functions[0]));
+        assert!(!super::is_generator(&functions[1]));
+        assert!(super::is_generator(&functions[2]));
+    }
+
+    #[test]
+    fn assert_get_yield_type() {
+        let yield_type = |source: &str| {
+            super::get_yield_type(&Expr::parse(source, "<test>").unwrap())
+                .and_then(|expr| get_annotation(&expr))
+        };
+
+        assert_eq!(yield_type("Iterator[User]"), Some("User".to_owned()));
+        assert_eq!(
+            yield_type("typing.Generator[Dict[str, int], None, None]"),
+            Some("Dict[str, int]".to_owned())
+        );
+        assert_eq!(yield_type("'AsyncIterator[User]'"), Some("User".to_owned()));
+        assert_eq!(yield_type("List[User]"), None);
+        assert_eq!(yield_type("User"), None);
+    }
+
+    #[test]
+    fn assert_get_fixture_arguments() {
+        let stmts = Suite::parse(
+            "@mock.patch('x')\ndef test(mocked, a, /, b, c=1, *args, d, e=2, **kwargs): pass\n",
+            "<test>",
+        )
+        .unwrap();
+        let functions = get_functions(&stmts);
+
+        assert_eq!(get_argument_names(&functions[0]), vec!["d"]);
+    }
+
+    #[test]
+    fn assert_get_class_test_cases() {
+        let stmts = Suite::parse(
+            "class TestA:\n    def test_a(self, a): pass\n    @staticmethod\n    def test_b(b): pass\n    def helper(self, c): pass\nclass Helper:\n    def test_c(self, c): pass\n",
+            "<test>",
+        )
+        .unwrap();
+        let test_cases = get_class_test_cases(&stmts);
+
+        let mut names: Vec<&String> = test_cases.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["TestA::test_a", "TestA::test_b"]);
+        assert_eq!(get_argument_names(&test_cases["TestA::test_a"]), vec!["a"]);
+        assert_eq!(get_argument_names(&test_cases["TestA::test_b"]), vec!["b"]);
+    }
+
+    #[test]
+    fn assert_get_partial_test_cases() {
+        let stmts = Suite::parse(
+            "def check(a, b, c, d): pass\ntest_check = functools.partial(check, 1, c=3)\n",
+            "<test>",
+        )
+        .unwrap();
+        let test_cases = get_partial_test_cases(&stmts, &get_functions(&stmts));
+
+        assert_eq!(
+            get_argument_names(&test_cases["test_check"]),
+            vec!["b", "d"]
+        );
+    }
 }
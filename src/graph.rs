@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use rustpython_ast::{Ranged, StmtFunctionDef};
+
+use crate::{
+    files::parsed_python_file::ParsedPythonFile,
+    fixture_index::{FixtureDefinition, FixtureIndex, FixtureSource},
+    functions::get_fixture_arguments,
+    listing::get_fixture_type,
+    nodes::{get_usefixtures, FixtureUtil},
+    usages::get_fixture_value_lookups,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Test,
+    Fixture,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    /// The test name, prefixed by its class for test methods, or the fixture name.
+    pub name: String,
+    pub kind: NodeKind,
+    pub filename: String,
+    pub line: usize,
+    /// Only set for fixtures.
+    pub scope: Option<String>,
+    /// The type of the value a fixture provides, suffixed with `(inferred)` when it is inferred
+    /// from the fixture's body.
+    pub return_type: Option<String>,
+}
+
+impl GraphNode {
+    fn test(name: &str, func: &StmtFunctionDef, file: &ParsedPythonFile) -> Self {
+        GraphNode {
+            name: name.to_owned(),
+            kind: NodeKind::Test,
+            filename: file.file.filename.clone(),
+            line: file.file.line_number(func.start()),
+            scope: None,
+            return_type: None,
+        }
+    }
+
+    fn fixture(fixture: &FixtureDefinition, index: &FixtureIndex) -> Self {
+        let return_type = get_fixture_type(fixture, index).map(|(annotation, inferred)| {
+            if inferred {
+                format!("{} (inferred)", annotation.written)
+            } else {
+                annotation.written
+            }
+        });
+
+        GraphNode {
+            name: fixture.function.name.to_string(),
+            kind: NodeKind::Fixture,
+            filename: fixture.filename.clone(),
+            line: fixture.line,
+            scope: Some(fixture.scope()),
+            return_type,
+        }
+    }
+
+    fn label_lines(&self) -> Vec<String> {
+        let mut lines = vec![self.name.clone()];
+        lines.extend(self.scope.iter().map(|scope| format!("scope: {}", scope)));
+        lines.extend(
+            self.return_type
+                .iter()
+                .map(|return_type| format!("type: {}", return_type)),
+        );
+        lines
+    }
+}
+
+/// A dependency of a test or fixture on a fixture. Autouse fixtures are used by every test that
+/// sees them without being requested.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub autouse: bool,
+}
+
+/// The dependencies of tests and fixtures on fixtures. Nodes are identified by their defining file
+/// and name, e.g. `tests/conftest.py::database`, since overridden fixtures share their name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FixtureGraph {
+    pub nodes: BTreeMap<String, GraphNode>,
+    pub edges: BTreeSet<GraphEdge>,
+}
+
+fn node_id(filename: &str, name: &str) -> String {
+    format!("{}::{}", filename, name)
+}
+
+/// Returns the fixture names a function requests through its arguments, `usefixtures` marks and
+/// `request.getfixturevalue` calls.
+fn get_requests(func: &StmtFunctionDef) -> Vec<String> {
+    let mut requests: Vec<String> = get_fixture_arguments(func)
+        .iter()
+        .map(|arg| arg.def.arg.to_string())
+        .collect();
+    requests.extend(func.decorator_list.iter().flat_map(get_usefixtures));
+    requests.extend(
+        get_fixture_value_lookups(&func.body)
+            .into_iter()
+            .map(|lookup| lookup.fixture_name),
+    );
+    requests
+}
+
+/// Resolves requests the way pytest does, from the fixtures visible from the file of the tests: a
+/// fixture requesting its own name receives the definition it overrides.
+struct GraphBuilder<'a> {
+    index: &'a FixtureIndex,
+    graph: FixtureGraph,
+    definitions: HashMap<String, Vec<(FixtureSource, FixtureDefinition)>>,
+    visited: HashSet<String>,
+}
+
+impl GraphBuilder<'_> {
+    fn add_dependency(&mut self, from: &str, name: &str, position: usize, autouse: bool) {
+        if let Some(to) = self.visit_fixture(name, position) {
+            self.graph.edges.insert(GraphEdge {
+                from: from.to_owned(),
+                to,
+                autouse,
+            });
+        }
+    }
+
+    fn visit_fixture(&mut self, name: &str, position: usize) -> Option<String> {
+        let (_, fixture) = self.definitions.get(name)?.get(position)?.clone();
+        let id = node_id(&fixture.filename, &fixture.function.name);
+
+        if self.visited.insert(id.clone()) {
+            self.graph
+                .nodes
+                .entry(id.clone())
+                .or_insert_with(|| GraphNode::fixture(&fixture, self.index));
+
+            for request in get_requests(&fixture.function) {
+                let position = if request == name { position + 1 } else { 0 };
+                self.add_dependency(&id, &request, position, false);
+            }
+        }
+
+        Some(id)
+    }
+}
+
+impl FixtureGraph {
+    /// Builds the graph of the tests and fixtures of `files`, along with the fixtures they depend
+    /// on wherever those are defined. Builtin fixtures are left out.
+    pub fn new(files: &[ParsedPythonFile], index: &FixtureIndex) -> Self {
+        let mut builder = GraphBuilder {
+            index,
+            graph: FixtureGraph::default(),
+            definitions: HashMap::new(),
+            visited: HashSet::new(),
+        };
+
+        for file in files {
+            builder.definitions = index.fixture_definitions(file);
+            builder.visited.clear();
+
+            let mut autouse: Vec<String> = builder
+                .definitions
+                .iter()
+                .filter(|(_, definitions)| {
+                    definitions
+                        .first()
+                        .is_some_and(|(_, fixture)| fixture.function.is_autouse_fixture())
+                })
+                .map(|(name, _)| name.clone())
+                .collect();
+            autouse.sort();
+
+            let mut tests: Vec<(&String, &StmtFunctionDef)> = file.test_cases.iter().collect();
+            tests.sort_by_key(|(name, _)| name.as_str());
+
+            for (name, func) in tests {
+                let id = node_id(&file.file.filename, name);
+                builder
+                    .graph
+                    .nodes
+                    .insert(id.clone(), GraphNode::test(name, func, file));
+
+                let requests = get_requests(func);
+                for request in requests.iter() {
+                    builder.add_dependency(&id, request, 0, false);
+                }
+                for name in autouse.iter().filter(|name| !requests.contains(name)) {
+                    builder.add_dependency(&id, name, 0, true);
+                }
+            }
+
+            // The fixtures of the file are part of the graph even when no test requests them.
+            let mut fixtures: Vec<&String> = file.fixtures.keys().collect();
+            fixtures.sort();
+            for name in fixtures {
+                builder.visit_fixture(name, 0);
+            }
+        }
+
+        builder.graph
+    }
+
+    /// Returns the subgraph of the nodes named `name` and of every fixture they depend on, directly
+    /// or not. Test methods can be named with or without their class.
+    pub fn closure(&self, name: &str) -> Result<FixtureGraph, String> {
+        let suffix = format!("::{}", name);
+        let mut pending: Vec<&String> = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| node.name == name || node.name.ends_with(&suffix))
+            .map(|(id, _)| id)
+            .collect();
+        if pending.is_empty() {
+            return Err(format!("No test or fixture named {}.", name));
+        }
+
+        let mut reached: BTreeSet<&String> = BTreeSet::new();
+        while let Some(id) = pending.pop() {
+            if reached.insert(id) {
+                pending.extend(
+                    self.edges
+                        .iter()
+                        .filter(|edge| &edge.from == id)
+                        .map(|edge| &edge.to),
+                );
+            }
+        }
+
+        Ok(FixtureGraph {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|(id, _)| reached.contains(id))
+                .map(|(id, node)| (id.clone(), node.clone()))
+                .collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|edge| reached.contains(&edge.from))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Renders the graph in Graphviz DOT format, with tests as ellipses, fixtures as boxes and the
+    /// edges to autouse fixtures dashed.
+    pub fn to_dot(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut lines = vec![
+            "digraph fixtures {".to_owned(),
+            "    rankdir=LR;".to_owned(),
+            "    node [shape=box];".to_owned(),
+        ];
+
+        for (id, node) in self.nodes.iter() {
+            let label: Vec<String> = node.label_lines().iter().map(|l| escape(l)).collect();
+            let shape = match node.kind {
+                NodeKind::Test => ", shape=ellipse",
+                NodeKind::Fixture => "",
+            };
+            lines.push(format!(
+                "    \"{}\" [label=\"{}\"{}];",
+                escape(id),
+                label.join("\\n"),
+                shape
+            ));
+        }
+        for edge in self.edges.iter() {
+            let style = if edge.autouse { " [style=dashed]" } else { "" };
+            lines.push(format!(
+                "    \"{}\" -> \"{}\"{};",
+                escape(&edge.from),
+                escape(&edge.to),
+                style
+            ));
+        }
+
+        lines.push("}".to_owned());
+        lines.join("\n")
+    }
+
+    /// Renders the graph as a Mermaid flowchart, with tests as stadiums, fixtures as rectangles and
+    /// the edges to autouse fixtures dotted.
+    pub fn to_mermaid(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        };
+        // Mermaid identifiers cannot hold the paths of node ids.
+        let ids: HashMap<&String, String> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(i, id)| (id, format!("n{}", i)))
+            .collect();
+        let mut lines = vec!["flowchart LR".to_owned()];
+
+        for (id, node) in self.nodes.iter() {
+            let label: Vec<String> = node.label_lines().iter().map(|l| escape(l)).collect();
+            let label = label.join("<br/>");
+            lines.push(match node.kind {
+                NodeKind::Test => format!("    {}([\"{}\"])", ids[id], label),
+                NodeKind::Fixture => format!("    {}[\"{}\"]", ids[id], label),
+            });
+        }
+        for edge in self.edges.iter() {
+            let arrow = if edge.autouse { "-.->" } else { "-->" };
+            lines.push(format!(
+                "    {} {} {}",
+                ids[&edge.from], arrow, ids[&edge.to]
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{load_and_index_files, settings::Settings};
+
+    use super::{FixtureGraph, GraphEdge};
+
+    fn get_graph() -> FixtureGraph {
+        let files = [
+            PathBuf::from("./python-examples/graph/conftest.py"),
+            PathBuf::from("./python-examples/graph/test_graph.py"),
+        ];
+        let (parsed, index) = load_and_index_files(files.iter(), &Settings::default());
+        FixtureGraph::new(&parsed, &index)
+    }
+
+    fn edge(from: &str, to: &str, autouse: bool) -> GraphEdge {
+        let id = |name: &str| {
+            let (file, name) = name.split_once(':').unwrap();
+            format!("./python-examples/graph/{}.py::{}", file, name)
+        };
+        GraphEdge {
+            from: id(from),
+            to: id(to),
+            autouse,
+        }
+    }
+
+    #[test]
+    fn assert_fixture_graph() {
+        let graph = get_graph();
+
+        assert_eq!(
+            graph.edges.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                edge("conftest:database", "conftest:settings", false),
+                edge("test_graph:TestUsers::test_user", "conftest:cleanup", true),
+                edge("test_graph:TestUsers::test_user", "test_graph:user", false),
+                edge("test_graph:database", "conftest:database", false),
+                edge("test_graph:test_settings", "conftest:cleanup", true),
+                edge("test_graph:test_settings", "conftest:settings", false),
+                edge("test_graph:user", "test_graph:database", false),
+            ]
+        );
+
+        let database = &graph.nodes["./python-examples/graph/conftest.py::database"];
+        assert_eq!(database.scope.as_deref(), Some("session"));
+        assert_eq!(database.return_type.as_deref(), Some("dict[str, str]"));
+    }
+
+    #[test]
+    fn assert_fixture_graph_closure() {
+        let graph = get_graph();
+        let closure = graph.closure("test_user").unwrap();
+
+        assert_eq!(closure.nodes.len(), 6);
+        assert!(!closure
+            .nodes
+            .contains_key("./python-examples/graph/test_graph.py::test_settings"));
+        assert_eq!(
+            graph.closure("missing"),
+            Err("No test or fixture named missing.".to_owned())
+        );
+    }
+
+    #[test]
+    fn assert_render_graph() {
+        let graph = get_graph().closure("database").unwrap();
+
+        assert_eq!(
+            graph.to_dot(),
+            [
+                "digraph fixtures {",
+                "    rankdir=LR;",
+                "    node [shape=box];",
+                "    \"./python-examples/graph/conftest.py::database\" [label=\"database\\nscope: session\\ntype: dict[str, str]\"];",
+                "    \"./python-examples/graph/conftest.py::settings\" [label=\"settings\\nscope: session\\ntype: Settings\"];",
+                "    \"./python-examples/graph/test_graph.py::database\" [label=\"database\\nscope: function\\ntype: dict[str, str]\"];",
+                "    \"./python-examples/graph/conftest.py::database\" -> \"./python-examples/graph/conftest.py::settings\";",
+                "    \"./python-examples/graph/test_graph.py::database\" -> \"./python-examples/graph/conftest.py::database\";",
+                "}",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            graph.to_mermaid(),
+            [
+                "flowchart LR",
+                "    n0[\"database<br/>scope: session<br/>type: dict[str, str]\"]",
+                "    n1[\"settings<br/>scope: session<br/>type: Settings\"]",
+                "    n2[\"database<br/>scope: function<br/>type: dict[str, str]\"]",
+                "    n0 --> n1",
+                "    n2 --> n0",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use rustpython_ast::{ArgWithDefault, Expr, ExprCall, StmtFunctionDef};
+use rustpython_parser::Parse;
+
+use crate::{
+    functions::{format_annotation, get_annotation},
+    imports::ImportTable,
+};
+
+/// Types generated by the `hypothesis.strategies` constructors that take no strategy argument.
+const SCALAR_STRATEGIES: &[(&str, &str)] = &[
+    ("binary", "bytes"),
+    ("booleans", "bool"),
+    ("characters", "str"),
+    ("complex_numbers", "complex"),
+    ("dates", "date"),
+    ("datetimes", "datetime"),
+    ("decimals", "Decimal"),
+    ("emails", "str"),
+    ("floats", "float"),
+    ("fractions", "Fraction"),
+    ("from_regex", "str"),
+    ("integers", "int"),
+    ("none", "None"),
+    ("text", "str"),
+    ("times", "time"),
+    ("timedeltas", "timedelta"),
+    ("uuids", "UUID"),
+];
+
+/// Strategy methods that keep the type generated by the strategy they are called on.
+const TYPE_PRESERVING_METHODS: &[&str] = &["filter"];
+
+fn get_callee_name(call: &ExprCall) -> Option<&str> {
+    match &*call.func {
+        Expr::Name(name) => Some(name.id.as_str()),
+        Expr::Attribute(attribute) => Some(attribute.attr.as_str()),
+        _ => None,
+    }
+}
+
+fn get_class_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Name(_) | Expr::Attribute(_) => get_annotation(expr),
+        _ => None,
+    }
+}
+
+/// Modules defining the types generated by the scalar strategies, and the `typing` aliases used for
+/// the generated collections, for when the checked module does not import them.
+const GENERATED_TYPE_MODULES: &[(&str, &str)] = &[
+    ("date", "datetime"),
+    ("datetime", "datetime"),
+    ("time", "datetime"),
+    ("timedelta", "datetime"),
+    ("Decimal", "decimal"),
+    ("Fraction", "fractions"),
+    ("UUID", "uuid"),
+    ("List", "typing"),
+    ("Set", "typing"),
+    ("FrozenSet", "typing"),
+    ("Dict", "typing"),
+    ("Tuple", "typing"),
+];
+
+/// Qualifies a type inferred by [`infer_strategy_type`] like an annotation written in the module
+/// with `imports`. The names the module does not bind are qualified with their defining module.
+pub fn qualify_generated_type(generated: &str, imports: &ImportTable) -> Option<String> {
+    let expr = Expr::parse(generated, "<strategy>").ok()?;
+
+    format_annotation(&expr, &|name| {
+        if imports.is_bound(name) {
+            return imports.resolve(name);
+        }
+        match GENERATED_TYPE_MODULES.iter().find(|(n, _)| *n == name) {
+            Some((name, module)) => format!("{}.{}", module, name),
+            None => name.to_owned(),
+        }
+    })
+}
+
+/// Infers the type of the values generated by a well-known `hypothesis.strategies` constructor,
+/// e.g. `int` for `st.integers()` or `List[str]` for `st.lists(st.text())`.
+pub fn infer_strategy_type(expr: &Expr) -> Option<String> {
+    let Expr::Call(call) = expr else {
+        return None;
+    };
+    let name = get_callee_name(call)?;
+
+    if TYPE_PRESERVING_METHODS.contains(&name) {
+        if let Expr::Attribute(attribute) = &*call.func {
+            return infer_strategy_type(&attribute.value);
+        }
+    }
+
+    if let Some((_, generated)) = SCALAR_STRATEGIES.iter().find(|(s, _)| *s == name) {
+        return Some(generated.to_string());
+    }
+
+    let first_argument = || infer_strategy_type(call.args.first()?);
+    match name {
+        "lists" => Some(format!("List[{}]", first_argument()?)),
+        "sets" => Some(format!("Set[{}]", first_argument()?)),
+        "frozensets" => Some(format!("FrozenSet[{}]", first_argument()?)),
+        "dictionaries" => {
+            let keys = infer_strategy_type(call.args.first()?)?;
+            let values = infer_strategy_type(call.args.get(1)?)?;
+            Some(format!("Dict[{}, {}]", keys, values))
+        }
+        "tuples" => {
+            let elements: Option<Vec<String>> = call.args.iter().map(infer_strategy_type).collect();
+            Some(format!("Tuple[{}]", elements?.join(", ")))
+        }
+        "builds" | "from_type" => get_class_name(call.args.first()?),
+        _ => None,
+    }
+}
+
+/// Returns the `@given(...)` decorator call of a function, if any.
+fn get_given(func: &StmtFunctionDef) -> Option<&ExprCall> {
+    func.decorator_list
+        .iter()
+        .find_map(|decorator| match decorator {
+            Expr::Call(call) if get_callee_name(call) == Some("given") => Some(call),
+            _ => None,
+        })
+}
+
+/// Returns the arguments bound by `@given`, together with the strategy providing them. Keyword
+/// strategies bind by name, positional strategies bind the rightmost positional arguments.
+pub fn get_given_bindings(func: &StmtFunctionDef) -> Vec<(&ArgWithDefault, &Expr)> {
+    let Some(given) = get_given(func) else {
+        return Vec::new();
+    };
+    let arguments: Vec<&ArgWithDefault> = func
+        .args
+        .posonlyargs
+        .iter()
+        .chain(func.args.args.iter())
+        .collect();
+
+    let positional = arguments
+        .iter()
+        .rev()
+        .zip(given.args.iter().rev())
+        .map(|(arg, strategy)| (*arg, strategy));
+    let keywords = given.keywords.iter().filter_map(|keyword| {
+        let name = keyword.arg.as_ref()?;
+        func.args
+            .args
+            .iter()
+            .chain(func.args.kwonlyargs.iter())
+            .find(|arg| arg.def.arg == *name)
+            .map(|arg| (arg, &keyword.value))
+    });
+
+    positional.chain(keywords).collect()
+}
+
+/// Returns the names of the arguments provided by hypothesis rather than by fixtures.
+pub fn get_given_argument_names(func: &StmtFunctionDef) -> Vec<String> {
+    get_given_bindings(func)
+        .into_iter()
+        .map(|(arg, _)| arg.def.arg.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_ast::{Expr, Stmt, Suite};
+    use rustpython_parser::Parse;
+
+    use crate::imports::ImportTable;
+
+    use super::{get_given_argument_names, infer_strategy_type, qualify_generated_type};
+
+    fn infer(source: &str) -> Option<String> {
+        infer_strategy_type(&Expr::parse(source, "<test>").unwrap())
+    }
+
+    #[test]
+    fn assert_infer_strategy_type() {
+        assert_eq!(infer("st.integers()"), Some("int".to_owned()));
+        assert_eq!(infer("text()"), Some("str".to_owned()));
+        assert_eq!(
+            infer("st.dictionaries(st.text(), st.lists(st.integers()))"),
+            Some("Dict[str, List[int]]".to_owned())
+        );
+        assert_eq!(
+            infer("st.tuples(st.integers(), st.booleans())"),
+            Some("Tuple[int, bool]".to_owned())
+        );
+        assert_eq!(
+            infer("st.builds(models.User)"),
+            Some("models.User".to_owned())
+        );
+        assert_eq!(infer("st.floats().filter(bool)"), Some("float".to_owned()));
+        assert_eq!(infer("st.integers().map(str)"), None);
+        assert_eq!(infer("st.sampled_from([1, 2])"), None);
+    }
+
+    #[test]
+    fn assert_get_given_argument_names() {
+        let stmts = Suite::parse(
+            "@given(st.integers(), st.text(), flag=st.booleans())\ndef test(fixture, a, b, *, flag): pass\n",
+            "<test>",
+        )
+        .unwrap();
+        let Stmt::FunctionDef(func) = &stmts[0] else {
+            panic!("Expected a function.")
+        };
+
+        assert_eq!(get_given_argument_names(func), vec!["b", "a", "flag"]);
+    }
+
+    #[test]
+    fn assert_qualify_generated_type() {
+        let stmts = Suite::parse(
+            "import datetime\nfrom typing import List\nfrom app import models\n",
+            "<test>",
+        )
+        .unwrap();
+        let imports = ImportTable::new(&stmts, "test_file.py");
+
+        assert_eq!(
+            qualify_generated_type("List[date]", &imports),
+            Some("typing.List[datetime.date]".to_owned())
+        );
+        assert_eq!(
+            qualify_generated_type("Dict[str, models.User]", &imports),
+            Some("typing.Dict[str, app.models.User]".to_owned())
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use std::{collections::HashMap, path::Path};
+
+use rustpython_ast::{ExceptHandler, Expr, Stmt, StmtTry, StmtTryStar};
+
+use crate::functions::{format_annotation, get_annotation};
+
+/// An annotation as written in the source and with its names fully qualified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedAnnotation {
+    pub written: String,
+    pub qualified: String,
+}
+
+/// Returns the names to display for two incompatible annotations. The qualified names are used
+/// when both are written the same way, e.g. two different classes named `Config`.
+pub fn describe_mismatch(
+    expected: ResolvedAnnotation,
+    provided: ResolvedAnnotation,
+) -> (String, String) {
+    if expected.written == provided.written {
+        (expected.qualified, provided.qualified)
+    } else {
+        (expected.written, provided.written)
+    }
+}
+
+/// Maps the names bound at module level to the fully qualified names they refer to, e.g. `np` to
+/// `numpy` for `import numpy as np`, or `Path` to `pathlib.Path` for `from pathlib import Path`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportTable {
+    pub module: String,
+    names: HashMap<String, String>,
+}
+
+/// Returns the dotted module name of a file, following the `__init__.py` files of its parent
+/// directories the way pytest's default import mode does.
+pub fn get_module_name(filename: &str) -> String {
+    let path = Path::new(filename);
+    let mut parts: Vec<String> = Vec::new();
+
+    if let Some(stem) = path.file_stem() {
+        if stem != "__init__" {
+            parts.push(stem.to_string_lossy().to_string());
+        }
+    }
+
+    for directory in path.ancestors().skip(1) {
+        let Some(name) = directory.file_name() else {
+            break;
+        };
+        if !directory.join("__init__.py").is_file() {
+            break;
+        }
+        parts.push(name.to_string_lossy().to_string());
+    }
+
+    parts.reverse();
+    parts.join(".")
+}
+
+fn is_package(filename: &str) -> bool {
+    Path::new(filename)
+        .file_stem()
+        .is_some_and(|stem| stem == "__init__")
+}
+
+/// Maps the names bound by `stmts` at module level, including those of conditional blocks such as
+/// `if TYPE_CHECKING:` and `try: ... except ImportError:`.
+fn insert_names(
+    names: &mut HashMap<String, String>,
+    stmts: &[Stmt],
+    module: &str,
+    package: &[&str],
+) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Import(import) => {
+                for alias in &import.names {
+                    match &alias.asname {
+                        Some(asname) => names.insert(asname.to_string(), alias.name.to_string()),
+                        None => {
+                            let top_level = alias.name.split('.').next().unwrap_or_default();
+                            names.insert(top_level.to_owned(), top_level.to_owned())
+                        }
+                    };
+                }
+            }
+            Stmt::ImportFrom(import) => {
+                let level = import.level.map(|l| l.to_usize()).unwrap_or_default();
+                let mut base: Vec<&str> = if level > 0 {
+                    package[..package.len().saturating_sub(level - 1)].to_vec()
+                } else {
+                    Vec::new()
+                };
+                if let Some(module) = &import.module {
+                    base.push(module.as_str());
+                }
+                let base = base.join(".");
+
+                for alias in import.names.iter().filter(|a| a.name.as_str() != "*") {
+                    let name = alias.asname.as_ref().unwrap_or(&alias.name);
+                    names.insert(name.to_string(), format!("{}.{}", base, alias.name));
+                }
+            }
+            Stmt::ClassDef(class) => {
+                names.insert(class.name.to_string(), format!("{}.{}", module, class.name));
+            }
+            Stmt::If(stmt) => {
+                insert_names(names, &stmt.body, module, package);
+                insert_names(names, &stmt.orelse, module, package);
+            }
+            Stmt::Try(StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            })
+            | Stmt::TryStar(StmtTryStar {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                insert_names(names, body, module, package);
+                for handler in handlers {
+                    let ExceptHandler::ExceptHandler(handler) = handler;
+                    insert_names(names, &handler.body, module, package);
+                }
+                insert_names(names, orelse, module, package);
+                insert_names(names, finalbody, module, package);
+            }
+            Stmt::Assign(assign) => {
+                for target in assign.targets.iter().filter_map(|t| t.as_name_expr()) {
+                    names.insert(target.id.to_string(), format!("{}.{}", module, target.id));
+                }
+            }
+            Stmt::TypeAlias(alias) => {
+                if let Some(target) = alias.name.as_name_expr() {
+                    names.insert(target.id.to_string(), format!("{}.{}", module, target.id));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ImportTable {
+    pub fn new(stmts: &[Stmt], filename: &str) -> Self {
+        let module = get_module_name(filename);
+        let mut names = HashMap::new();
+
+        let package: Vec<&str> = if is_package(filename) {
+            module.split('.').collect()
+        } else {
+            module
+                .split('.')
+                .collect::<Vec<_>>()
+                .split_last()
+                .map(|(_, p)| p.to_vec())
+                .unwrap_or_default()
+        };
+
+        insert_names(&mut names, stmts, &module, &package);
+
+        ImportTable { module, names }
+    }
+
+    /// Returns the fully qualified name a module-level name refers to. Names that are neither
+    /// imported nor defined in the module, such as builtins, are returned as is.
+    pub fn resolve(&self, name: &str) -> String {
+        self.names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Whether a name is imported or defined at module level.
+    pub fn is_bound(&self, name: &str) -> bool {
+        self.names.contains_key(name)
+    }
+
+    /// Returns how a fully qualified name can be written in the module, using the name bound to it
+    /// or to one of its parent modules, e.g. `np.ndarray` for `numpy.ndarray` after
+    /// `import numpy as np`.
+    pub fn find_binding(&self, qualified: &str) -> Option<String> {
+        let mut prefix = qualified;
+        loop {
+            let binding = self
+                .names
+                .iter()
+                .filter(|(_, target)| target.as_str() == prefix)
+                .map(|(name, _)| name)
+                .min();
+            if let Some(binding) = binding {
+                return Some(format!("{}{}", binding, &qualified[prefix.len()..]));
+            }
+            prefix = prefix.rsplit_once('.')?.0;
+        }
+    }
+
+    /// Formats an annotation with every name replaced by its fully qualified name, so that `Path`
+    /// and `pathlib.Path` compare equal.
+    pub fn qualify_annotation(&self, expr: &Expr) -> Option<String> {
+        format_annotation(expr, &|name| self.resolve(name))
+    }
+
+    pub fn resolve_annotation(&self, expr: &Expr) -> Option<ResolvedAnnotation> {
+        Some(ResolvedAnnotation {
+            written: get_annotation(expr)?,
+            qualified: self.qualify_annotation(expr)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_ast::{Expr, Suite};
+    use rustpython_parser::Parse;
+
+    use super::{get_module_name, ImportTable};
+
+    #[test]
+    fn assert_get_module_name() {
+        assert_eq!(
+            get_module_name("./python-examples/plugins/shared/db.py"),
+            "shared.db"
+        );
+        assert_eq!(
+            get_module_name("./python-examples/plugins/shared/__init__.py"),
+            "shared"
+        );
+        assert_eq!(
+            get_module_name("./python-examples/test_sample.py"),
+            "test_sample"
+        );
+    }
+
+    #[test]
+    fn assert_qualify_annotation() {
+        let stmts = Suite::parse(
+            "import numpy as np\nimport os.path\nimport typing as t\nfrom pathlib import Path\nfrom ..models import User as U\nclass Config: pass\n",
+            "<test>",
+        )
+        .unwrap();
+        let imports = ImportTable::new(&stmts, "./python-examples/plugins/shared/db.py");
+        let qualify =
+            |source: &str| imports.qualify_annotation(&Expr::parse(source, "<test>").unwrap());
+
+        assert_eq!(qualify("np.ndarray"), Some("numpy.ndarray".to_owned()));
+        assert_eq!(
+            qualify("os.path.PathLike"),
+            Some("os.path.PathLike".to_owned())
+        );
+        assert_eq!(
+            qualify("t.Dict[str, Path]"),
+            Some("typing.Dict[str, pathlib.Path]".to_owned())
+        );
+        assert_eq!(qualify("U"), Some("models.User".to_owned()));
+        assert_eq!(qualify("Config"), Some("shared.db.Config".to_owned()));
+    }
+
+    #[test]
+    fn assert_qualify_conditional_imports() {
+        let stmts = Suite::parse(
+            "if TYPE_CHECKING:\n    from pathlib import Path\nelif sys.version_info < (3, 8):\n    from typing_extensions import Literal\ntry:\n    import simplejson as json\nexcept ImportError:\n    from decimal import Decimal\nfinally:\n    from enum import Enum\n",
+            "<test>",
+        )
+        .unwrap();
+        let imports = ImportTable::new(&stmts, "test_file.py");
+        let qualify =
+            |source: &str| imports.qualify_annotation(&Expr::parse(source, "<test>").unwrap());
+
+        assert_eq!(qualify("Path"), Some("pathlib.Path".to_owned()));
+        assert_eq!(
+            qualify("Literal"),
+            Some("typing_extensions.Literal".to_owned())
+        );
+        assert_eq!(
+            qualify("json.JSONDecoder"),
+            Some("simplejson.JSONDecoder".to_owned())
+        );
+        assert_eq!(qualify("Decimal"), Some("decimal.Decimal".to_owned()));
+        assert_eq!(qualify("Enum"), Some("enum.Enum".to_owned()));
+    }
+
+    #[test]
+    fn assert_find_binding() {
+        let stmts = Suite::parse(
+            "import numpy as np\nimport os.path\nfrom pathlib import Path\n",
+            "<test>",
+        )
+        .unwrap();
+        let imports = ImportTable::new(&stmts, "test_file.py");
+
+        assert_eq!(
+            imports.find_binding("numpy.ndarray"),
+            Some("np.ndarray".to_owned())
+        );
+        assert_eq!(
+            imports.find_binding("os.path.PathLike"),
+            Some("os.path.PathLike".to_owned())
+        );
+        assert_eq!(
+            imports.find_binding("pathlib.Path"),
+            Some("Path".to_owned())
+        );
+        assert_eq!(imports.find_binding("pathlib.PurePath"), None);
+        assert!(imports.is_bound("np"));
+        assert!(!imports.is_bound("numpy"));
+    }
+}
@@ -0,0 +1,784 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use rustpython_ast::{
+    Constant, Expr, ExprContext, ExprLambda, ExprName, ExprYield, ExprYieldFrom, Operator, Ranged,
+    Stmt, StmtAsyncFunctionDef, StmtClassDef, StmtFunctionDef, StmtReturn, TextSize, Visitor,
+};
+use rustpython_parser_vendored::text_size::TextRange;
+
+use rustpython_parser::Parse;
+
+use crate::{
+    files::parsed_python_file::ParsedPythonFile,
+    fixture_index::{FixtureDefinition, FixtureIndex},
+    functions::{get_annotation, get_argument_annotation, get_fixture_arguments},
+    imports::ImportTable,
+    nodes::FixtureUtil,
+    render::requested_fixture,
+};
+
+const BUILTIN_TYPES: [&str; 10] = [
+    "bool",
+    "bytes",
+    "complex",
+    "dict",
+    "float",
+    "frozenset",
+    "int",
+    "list",
+    "set",
+    "str",
+];
+
+/// Assignments to the same variable are not followed deeper than this, e.g. for `x = x + 1`.
+const MAX_DEPTH: usize = 8;
+
+/// The type of a fixture's value, inferred from its body.
+#[derive(Debug, Clone)]
+pub enum InferredType {
+    /// A builtin type or a class, written as in the fixture's module.
+    Name(String),
+    /// A builtin generic type such as `list[int]`.
+    Generic(String, Vec<InferredType>),
+    Union(Vec<InferredType>),
+    /// The values yielded by a generator fixture, annotated as `Iterator[T]`.
+    Iterator(Box<InferredType>),
+    /// The value of another fixture, which has a return annotation.
+    Fixture(Box<FixtureDefinition>),
+}
+
+impl PartialEq for InferredType {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+fn join_types(types: &[InferredType], separator: &str) -> String {
+    let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+    types.join(separator)
+}
+
+impl Display for InferredType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InferredType::Name(name) => write!(f, "{}", name),
+            InferredType::Generic(name, arguments) => {
+                write!(f, "{}[{}]", name, join_types(arguments, ", "))
+            }
+            InferredType::Union(types) => write!(f, "{}", join_types(types, " | ")),
+            InferredType::Iterator(value) => write!(f, "Iterator[{}]", value),
+            InferredType::Fixture(fixture) => {
+                let annotation = fixture.value_annotation().and_then(|a| get_annotation(&a));
+                write!(f, "{}", annotation.unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// Aliases of builtin generic types from `typing`, which are the same types as the builtins.
+const TYPING_ALIASES: [(&str, &str); 6] = [
+    ("typing.List", "list"),
+    ("typing.Dict", "dict"),
+    ("typing.Set", "set"),
+    ("typing.FrozenSet", "frozenset"),
+    ("typing.Tuple", "tuple"),
+    ("typing.Type", "type"),
+];
+
+fn is_delimiter(c: char) -> bool {
+    matches!(c, '[' | ']' | ',' | '|' | ' ')
+}
+
+fn normalize_alias(name: &str) -> &str {
+    TYPING_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, builtin)| builtin)
+}
+
+/// Replaces the `typing` aliases of builtin types in a qualified annotation, so that an inferred
+/// `list[int]` matches `List[int]`. Only whole dotted names are replaced.
+pub fn normalize_builtin_aliases(qualified: &str) -> String {
+    qualified
+        .split_inclusive(is_delimiter)
+        .map(|segment| {
+            let name = segment.trim_end_matches(is_delimiter);
+            format!("{}{}", normalize_alias(name), &segment[name.len()..])
+        })
+        .collect()
+}
+
+/// Implicit conversions between numeric types accepted by type checkers, e.g. an `int` passed where
+/// a `float` is expected.
+const NUMERIC_PROMOTIONS: [(&str, &str); 6] = [
+    ("bool", "int"),
+    ("bool", "float"),
+    ("bool", "complex"),
+    ("int", "float"),
+    ("int", "complex"),
+    ("float", "complex"),
+];
+
+/// The abstract collection types the builtin types implement, from `typing` or
+/// `collections.abc`.
+const ABSTRACT_BASES: [(&str, &[&str]); 6] = [
+    (
+        "list",
+        &["Sequence", "MutableSequence", "Collection", "Iterable"],
+    ),
+    ("tuple", &["Sequence", "Collection", "Iterable"]),
+    (
+        "dict",
+        &["Mapping", "MutableMapping", "Collection", "Iterable"],
+    ),
+    (
+        "set",
+        &["AbstractSet", "MutableSet", "Collection", "Iterable"],
+    ),
+    ("frozenset", &["AbstractSet", "Collection", "Iterable"]),
+    ("str", &["Sequence", "Collection", "Iterable"]),
+];
+
+/// A qualified annotation in a form where equivalent annotations compare equal: unions are sets
+/// and `Optional[T]` is `T | None`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum NormalizedType {
+    Name(String),
+    Generic(String, Vec<NormalizedType>),
+    Union(BTreeSet<NormalizedType>),
+}
+
+fn normalize_union(members: Vec<NormalizedType>) -> NormalizedType {
+    let mut union: BTreeSet<NormalizedType> = BTreeSet::new();
+    for member in members {
+        match member {
+            NormalizedType::Union(members) => union.extend(members),
+            member => {
+                union.insert(member);
+            }
+        }
+    }
+
+    if union.len() == 1 {
+        union
+            .pop_first()
+            .unwrap_or(NormalizedType::Union(BTreeSet::new()))
+    } else {
+        NormalizedType::Union(union)
+    }
+}
+
+fn normalize_type(expr: &Expr) -> Option<NormalizedType> {
+    match expr {
+        Expr::Constant(constant) if constant.value.is_none() => {
+            Some(NormalizedType::Name("None".to_owned()))
+        }
+        Expr::Name(_) | Expr::Attribute(_) => Some(NormalizedType::Name(
+            normalize_alias(&get_dotted_name(expr)?).to_owned(),
+        )),
+        Expr::BinOp(binop) if binop.op == Operator::BitOr => Some(normalize_union(vec![
+            normalize_type(&binop.left)?,
+            normalize_type(&binop.right)?,
+        ])),
+        Expr::Subscript(subscript) => {
+            let name = get_dotted_name(&subscript.value)?;
+            let arguments: Option<Vec<NormalizedType>> = match &*subscript.slice {
+                Expr::Tuple(tuple) => tuple.elts.iter().map(normalize_type).collect(),
+                slice => vec![normalize_type(slice)].into_iter().collect(),
+            };
+            let mut arguments = arguments?;
+
+            match name.as_str() {
+                "typing.Optional" => {
+                    arguments.push(NormalizedType::Name("None".to_owned()));
+                    Some(normalize_union(arguments))
+                }
+                "typing.Union" => Some(normalize_union(arguments)),
+                name => Some(NormalizedType::Generic(
+                    normalize_alias(name).to_owned(),
+                    arguments,
+                )),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether a builtin type is, or implements, the type named `target`.
+fn is_subtype_name(value: &str, target: &str) -> bool {
+    let abstract_name = target
+        .strip_prefix("typing.")
+        .or_else(|| target.strip_prefix("collections.abc."));
+
+    value == target
+        || NUMERIC_PROMOTIONS.contains(&(value, target))
+        || abstract_name.is_some_and(|abstract_name| {
+            ABSTRACT_BASES
+                .iter()
+                .any(|(builtin, bases)| *builtin == value && bases.contains(&abstract_name))
+        })
+}
+
+fn is_assignable_type(value: &NormalizedType, target: &NormalizedType) -> bool {
+    match (value, target) {
+        (_, NormalizedType::Name(target)) if target == "typing.Any" || target == "object" => true,
+        (NormalizedType::Union(values), _) => values.iter().all(|v| is_assignable_type(v, target)),
+        (_, NormalizedType::Union(targets)) => targets.iter().any(|t| is_assignable_type(value, t)),
+        (NormalizedType::Name(value), NormalizedType::Name(target)) => {
+            is_subtype_name(value, target)
+        }
+        // An empty literal such as `[]` is inferred as a bare generic, whose elements are unknown.
+        (NormalizedType::Name(value), NormalizedType::Generic(target, _))
+        | (NormalizedType::Generic(value, _), NormalizedType::Name(target)) => {
+            is_subtype_name(value, target)
+        }
+        (
+            NormalizedType::Generic(value, value_arguments),
+            NormalizedType::Generic(target, target_arguments),
+        ) => {
+            is_subtype_name(value, target)
+                && value_arguments
+                    .iter()
+                    .zip(target_arguments.iter())
+                    .all(|(v, t)| is_assignable_type(v, t))
+        }
+    }
+}
+
+/// Whether a value of an inferred type can be passed where `annotation` is expected, both being
+/// qualified annotations. Unions are compared regardless of their order, `Optional[T]` is
+/// `T | None` and numbers are promoted from `int` to `float` to `complex`. Types that cannot be
+/// compared are assumed to be compatible.
+pub fn is_assignable(inferred: &str, annotation: &str) -> bool {
+    let normalize = |source: &str| normalize_type(&Expr::parse(source, "<annotation>").ok()?);
+
+    match (normalize(inferred), normalize(annotation)) {
+        (Some(inferred), Some(annotation)) => is_assignable_type(&inferred, &annotation),
+        _ => true,
+    }
+}
+
+impl InferredType {
+    /// The type of the value requesting functions receive, which is the yielded one for a
+    /// generator fixture.
+    pub fn value_type(&self) -> &InferredType {
+        match self {
+            InferredType::Iterator(value) => value,
+            inferred => inferred,
+        }
+    }
+
+    /// Formats the type with fully qualified names, resolving the names written in the fixture's
+    /// module through its imports.
+    pub fn qualify(&self, imports: &ImportTable) -> Option<String> {
+        let qualify_all = |types: &[InferredType], separator: &str| -> Option<String> {
+            let qualified: Option<Vec<String>> = types.iter().map(|t| t.qualify(imports)).collect();
+            Some(qualified?.join(separator))
+        };
+
+        match self {
+            InferredType::Name(name) => {
+                imports.qualify_annotation(&Expr::parse(name, "<inferred>").ok()?)
+            }
+            InferredType::Generic(name, arguments) => {
+                Some(format!("{}[{}]", name, qualify_all(arguments, ", ")?))
+            }
+            InferredType::Union(types) => qualify_all(types, " | "),
+            InferredType::Iterator(value) => {
+                Some(format!("typing.Iterator[{}]", value.qualify(imports)?))
+            }
+            InferredType::Fixture(fixture) => Some(fixture.return_annotation()?.qualified),
+        }
+    }
+}
+
+/// Merges the types of the values an expression may take, e.g. from several `return` statements.
+fn merge(types: Vec<InferredType>) -> Option<InferredType> {
+    let mut merged: Vec<InferredType> = Vec::new();
+    for inferred in types {
+        let inferred = match inferred {
+            InferredType::Union(types) => types,
+            inferred => vec![inferred],
+        };
+        for inferred in inferred {
+            if !merged.contains(&inferred) {
+                merged.push(inferred);
+            }
+        }
+    }
+
+    match merged.len() {
+        0 => None,
+        1 => merged.pop(),
+        _ => Some(InferredType::Union(merged)),
+    }
+}
+
+#[derive(Default)]
+struct ValuesVisitor {
+    returns: Vec<Option<Expr>>,
+    yields: Vec<Option<Expr>>,
+    yields_from: bool,
+}
+
+impl Visitor for ValuesVisitor {
+    fn visit_stmt_return(&mut self, node: StmtReturn) {
+        self.returns.push(node.value.map(|value| *value));
+    }
+
+    fn visit_expr_yield(&mut self, node: ExprYield) {
+        self.yields.push(node.value.map(|value| *value));
+    }
+
+    fn visit_expr_yield_from(&mut self, _node: ExprYieldFrom) {
+        self.yields_from = true;
+    }
+
+    fn visit_stmt_function_def(&mut self, _node: StmtFunctionDef) {}
+
+    fn visit_stmt_async_function_def(&mut self, _node: StmtAsyncFunctionDef) {}
+
+    fn visit_stmt_class_def(&mut self, _node: StmtClassDef) {}
+
+    fn visit_expr_lambda(&mut self, _node: ExprLambda) {}
+}
+
+/// Collects the ranges of the nodes binding a name in the scope of a function's body.
+struct StoredNameVisitor<'a> {
+    name: &'a str,
+    ranges: Vec<TextRange>,
+}
+
+impl Visitor for StoredNameVisitor<'_> {
+    fn visit_expr_name(&mut self, node: ExprName) {
+        if node.ctx == ExprContext::Store && node.id.as_str() == self.name {
+            self.ranges.push(node.range());
+        }
+    }
+
+    // Nested definitions only bind their own name in the function's scope.
+    fn visit_stmt_function_def(&mut self, node: StmtFunctionDef) {
+        if node.name.as_str() == self.name {
+            self.ranges.push(node.range());
+        }
+    }
+
+    fn visit_stmt_async_function_def(&mut self, node: StmtAsyncFunctionDef) {
+        if node.name.as_str() == self.name {
+            self.ranges.push(node.range());
+        }
+    }
+
+    fn visit_stmt_class_def(&mut self, node: StmtClassDef) {
+        if node.name.as_str() == self.name {
+            self.ranges.push(node.range());
+        }
+    }
+
+    fn visit_expr_lambda(&mut self, _node: ExprLambda) {}
+}
+
+struct Inference<'a> {
+    func: &'a StmtFunctionDef,
+    file: &'a ParsedPythonFile,
+    index: &'a FixtureIndex,
+    fixtures: &'a HashMap<String, FixtureDefinition>,
+}
+
+/// Whether a name, possibly dotted, looks like a class following the usual naming convention.
+fn is_class_name(name: &str) -> bool {
+    name.rsplit('.')
+        .next()
+        .and_then(|name| name.chars().next())
+        .is_some_and(|c| c.is_ascii_uppercase())
+}
+
+fn get_dotted_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Name(name) => Some(name.id.to_string()),
+        Expr::Attribute(attribute) => Some(format!(
+            "{}.{}",
+            get_dotted_name(&attribute.value)?,
+            attribute.attr
+        )),
+        _ => None,
+    }
+}
+
+impl Inference<'_> {
+    fn infer_all(&self, exprs: &[Expr], depth: usize) -> Option<Vec<InferredType>> {
+        exprs.iter().map(|e| self.infer(e, depth)).collect()
+    }
+
+    fn infer_elements(&self, name: &str, exprs: &[Expr], depth: usize) -> Option<InferredType> {
+        match merge(self.infer_all(exprs, depth)?) {
+            Some(element) => Some(InferredType::Generic(name.to_owned(), vec![element])),
+            None => Some(InferredType::Name(name.to_owned())),
+        }
+    }
+
+    fn infer_constant(&self, value: &Constant) -> Option<InferredType> {
+        let name = match value {
+            Constant::None => "None",
+            Constant::Bool(_) => "bool",
+            Constant::Str(_) => "str",
+            Constant::Bytes(_) => "bytes",
+            Constant::Int(_) => "int",
+            Constant::Float(_) => "float",
+            Constant::Complex { .. } => "complex",
+            _ => return None,
+        };
+        Some(InferredType::Name(name.to_owned()))
+    }
+
+    /// Infers the type of `request.param` from the fixture's literal `params`.
+    fn infer_param(&self, depth: usize) -> Option<InferredType> {
+        match self.func.fixture_keyword("params")? {
+            Expr::List(list) => merge(self.infer_all(&list.elts, depth)?),
+            Expr::Tuple(tuple) => merge(self.infer_all(&tuple.elts, depth)?),
+            _ => None,
+        }
+    }
+
+    fn infer_call(&self, func: &Expr) -> Option<InferredType> {
+        let name = get_dotted_name(func)?;
+        let root = name.split('.').next().unwrap_or_default();
+
+        if BUILTIN_TYPES.contains(&name.as_str()) && !self.file.imports.is_bound(&name) {
+            return Some(InferredType::Name(name));
+        }
+        if self.file.imports.is_bound(root) && is_class_name(&name) {
+            return Some(InferredType::Name(name));
+        }
+        None
+    }
+
+    /// Returns the value of the assignment to `name` in the fixture's body that provides its value
+    /// when read at `at`: the last assignment at the top level of the body ending before `at`, when
+    /// no other statement binds the name after it, such as a conditional assignment or a loop
+    /// rebinding it after `at`. Returns `Ok(None)` for a name the body never binds.
+    fn get_assigned_value(&self, name: &str, at: TextSize) -> Result<Option<&Expr>, ()> {
+        let mut visitor = StoredNameVisitor {
+            name,
+            ranges: Vec::new(),
+        };
+        for stmt in self.func.body.iter() {
+            visitor.visit_stmt(stmt.clone());
+        }
+        if visitor.ranges.is_empty() {
+            return Ok(None);
+        }
+
+        let (range, value) = self
+            .func
+            .body
+            .iter()
+            .rev()
+            .filter(|stmt| stmt.end() <= at)
+            .find_map(|stmt| match stmt {
+                Stmt::Assign(assign)
+                    if assign.targets.len() == 1
+                        && assign.targets[0]
+                            .as_name_expr()
+                            .is_some_and(|target| target.id.as_str() == name) =>
+                {
+                    Some((assign.range(), &*assign.value))
+                }
+                Stmt::AnnAssign(assign)
+                    if assign
+                        .target
+                        .as_name_expr()
+                        .is_some_and(|target| target.id.as_str() == name) =>
+                {
+                    Some((assign.range(), assign.value.as_deref()?))
+                }
+                _ => None,
+            })
+            .ok_or(())?;
+
+        visitor
+            .ranges
+            .iter()
+            .all(|store| store.end() <= range.start() || range.contains_range(*store))
+            .then_some(Some(value))
+            .ok_or(())
+    }
+
+    /// Infers the type of a variable read at `at` from the assignment providing its value in the
+    /// fixture's body or, when it is an argument the body never binds, from its annotation or the
+    /// fixture it receives.
+    fn infer_name(&self, name: &str, at: TextSize, depth: usize) -> Option<InferredType> {
+        if let Some(value) = self.get_assigned_value(name, at).ok()? {
+            return self.infer(value, depth + 1);
+        }
+
+        let argument = get_fixture_arguments(self.func)
+            .into_iter()
+            .find(|arg| arg.def.arg.as_str() == name)?;
+        if let Some(annotation) = get_argument_annotation(argument) {
+            return Some(InferredType::Name(annotation));
+        }
+        let fixture =
+            requested_fixture(self.file, self.index, self.fixtures, &self.func.name, name)?;
+        fixture.function.returns.as_ref()?;
+        Some(InferredType::Fixture(Box::new(fixture)))
+    }
+
+    fn infer(&self, expr: &Expr, depth: usize) -> Option<InferredType> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        match expr {
+            Expr::Constant(constant) => self.infer_constant(&constant.value),
+            Expr::JoinedStr(_) => Some(InferredType::Name("str".to_owned())),
+            Expr::List(list) => self.infer_elements("list", &list.elts, depth),
+            Expr::Set(set) => self.infer_elements("set", &set.elts, depth),
+            Expr::Tuple(tuple) if tuple.elts.is_empty() => {
+                Some(InferredType::Name("tuple".to_owned()))
+            }
+            Expr::Tuple(tuple) => Some(InferredType::Generic(
+                "tuple".to_owned(),
+                self.infer_all(&tuple.elts, depth)?,
+            )),
+            Expr::Dict(dict) if dict.values.is_empty() => {
+                Some(InferredType::Name("dict".to_owned()))
+            }
+            Expr::Dict(dict) => {
+                // A `None` key is a `**mapping` unpacking, whose types are unknown.
+                let keys: Option<Vec<Expr>> = dict.keys.iter().cloned().collect();
+                Some(InferredType::Generic(
+                    "dict".to_owned(),
+                    vec![
+                        merge(self.infer_all(&keys?, depth)?)?,
+                        merge(self.infer_all(&dict.values, depth)?)?,
+                    ],
+                ))
+            }
+            Expr::Call(call) => self.infer_call(&call.func),
+            Expr::Name(name) => self.infer_name(&name.id, name.start(), depth),
+            Expr::Attribute(attribute)
+                if attribute.attr.as_str() == "param"
+                    && attribute
+                        .value
+                        .as_name_expr()
+                        .is_some_and(|name| name.id.as_str() == "request") =>
+            {
+                self.infer_param(depth)
+            }
+            _ => None,
+        }
+    }
+
+    fn infer_values(&self, values: &[Option<Expr>]) -> Option<InferredType> {
+        let types: Option<Vec<InferredType>> = values
+            .iter()
+            .map(|value| match value {
+                Some(value) => self.infer(value, 0),
+                None => Some(InferredType::Name("None".to_owned())),
+            })
+            .collect();
+        merge(types?)
+    }
+}
+
+/// Infers the type of the value a fixture provides from its `return` or `yield` statements.
+/// Literals, calls of builtin types and of names that look like classes, `request.param` with
+/// literal `params` and the values of other annotated fixtures are understood. Returns `None` when
+/// any of the values cannot be inferred.
+pub fn infer_fixture_type(
+    func: &StmtFunctionDef,
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+    fixtures: &HashMap<String, FixtureDefinition>,
+) -> Option<InferredType> {
+    let mut visitor = ValuesVisitor::default();
+    for stmt in func.body.iter() {
+        visitor.visit_stmt(stmt.clone());
+    }
+    let inference = Inference {
+        func,
+        file,
+        index,
+        fixtures,
+    };
+
+    if visitor.yields_from {
+        return None;
+    }
+    if !visitor.yields.is_empty() {
+        let value = inference.infer_values(&visitor.yields)?;
+        return Some(InferredType::Iterator(Box::new(value)));
+    }
+    if visitor.returns.is_empty() {
+        return Some(InferredType::Name("None".to_owned()));
+    }
+    inference.infer_values(&visitor.returns)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        files::python_file::PythonFile,
+        fixture_index::{FixtureDefinition, FixtureIndex},
+    };
+
+    use super::{infer_fixture_type, is_assignable, normalize_builtin_aliases};
+
+    fn infer(source: &str) -> Option<String> {
+        let file = PythonFile::parse(PythonFile {
+            content: source.to_owned(),
+            filename: "test_file.py".to_owned(),
+        });
+        let fixtures: HashMap<String, FixtureDefinition> = file
+            .fixtures
+            .iter()
+            .map(|(name, func)| (name.clone(), FixtureDefinition::new(func, &file)))
+            .collect();
+        let func = file.fixtures.get("value")?;
+
+        infer_fixture_type(func, &file, &FixtureIndex::default(), &fixtures).map(|t| t.to_string())
+    }
+
+    #[test]
+    fn assert_infer_literals() {
+        let fixture = |body: &str| {
+            infer(&format!(
+                "import pytest\n@pytest.fixture()\ndef value():\n    {}\n",
+                body
+            ))
+        };
+
+        assert_eq!(fixture("return 1"), Some("int".to_owned()));
+        assert_eq!(fixture("return f'{1}'"), Some("str".to_owned()));
+        assert_eq!(fixture("return [1, 2]"), Some("list[int]".to_owned()));
+        assert_eq!(
+            fixture("return {'a': [1.0], 'b': [2.0]}"),
+            Some("dict[str, list[float]]".to_owned())
+        );
+        assert_eq!(
+            fixture("return (1, 'a')"),
+            Some("tuple[int, str]".to_owned())
+        );
+        assert_eq!(
+            fixture("if x:\n        return 1\n    return None"),
+            Some("int | None".to_owned())
+        );
+        assert_eq!(fixture("pass"), Some("None".to_owned()));
+        assert_eq!(fixture("return compute()"), None);
+        assert_eq!(fixture("return 1 + 2"), None);
+    }
+
+    #[test]
+    fn assert_infer_classes_and_fixtures() {
+        let source = r#"
+import pytest
+from app import models
+from app.models import User
+
+@pytest.fixture()
+def user() -> User:
+    return User()
+
+@pytest.fixture()
+def value(user):
+    account = models.Account(user)
+    yield account
+"#;
+        assert_eq!(infer(source), Some("Iterator[models.Account]".to_owned()));
+
+        let source = source.replace("    yield account", "    return user");
+        assert_eq!(infer(&source), Some("User".to_owned()));
+    }
+
+    #[test]
+    fn assert_infer_assigned_names() {
+        let fixture = |body: &str| {
+            infer(&format!(
+                "import pytest\n@pytest.fixture()\ndef value(user: int):\n{}\n",
+                body
+            ))
+        };
+
+        assert_eq!(
+            fixture("    x = 'a'\n    x = 1\n    return x"),
+            Some("int".to_owned())
+        );
+        assert_eq!(
+            fixture("    if user:\n        x = 'a'\n    x = 1\n    return x"),
+            Some("int".to_owned())
+        );
+        assert_eq!(fixture("    return user"), Some("int".to_owned()));
+        // Assigned after the value is provided.
+        assert_eq!(fixture("    x = 1\n    yield x\n    x = 'a'"), None);
+        // Conditionally rebound before the value is provided.
+        assert_eq!(
+            fixture("    x = 1\n    if user:\n        x = 'a'\n    return x"),
+            None
+        );
+        assert_eq!(fixture("    if user:\n        x = 1\n    return x"), None);
+        // Rebound in a loop after the value is provided.
+        assert_eq!(
+            fixture("    x = 1\n    for i in range(2):\n        yield x\n        x = 'a'"),
+            None
+        );
+        assert_eq!(
+            fixture("    user = 'a'\n    return user"),
+            Some("str".to_owned())
+        );
+        assert_eq!(
+            fixture("    if user:\n        user = 'a'\n    return user"),
+            None
+        );
+    }
+
+    #[test]
+    fn assert_infer_params() {
+        assert_eq!(
+            infer(
+                "import pytest\n@pytest.fixture(params=[1, 2, None])\ndef value(request):\n    return request.param\n"
+            ),
+            Some("int | None".to_owned())
+        );
+    }
+
+    #[test]
+    fn assert_normalize_builtin_aliases() {
+        assert_eq!(
+            normalize_builtin_aliases("typing.Dict[str, typing.List[int]]"),
+            "dict[str, list[int]]"
+        );
+        assert_eq!(
+            normalize_builtin_aliases("typing.TypedDict | typing.TypeGuard[int]"),
+            "typing.TypedDict | typing.TypeGuard[int]"
+        );
+        assert_eq!(normalize_builtin_aliases("mytyping.List"), "mytyping.List");
+    }
+
+    #[test]
+    fn assert_is_assignable() {
+        assert!(is_assignable("list", "list[int]"));
+        assert!(is_assignable("dict", "dict[str, int]"));
+        assert!(is_assignable("list[int]", "typing.List[int]"));
+        assert!(is_assignable("int | None", "typing.Optional[int]"));
+        assert!(is_assignable("None | int", "typing.Union[int, None]"));
+        assert!(is_assignable("int", "float"));
+        assert!(is_assignable("int", "typing.Optional[complex]"));
+        assert!(is_assignable("list[int]", "typing.Sequence[float]"));
+        assert!(is_assignable(
+            "dict[str, int]",
+            "collections.abc.Mapping[str, int]"
+        ));
+        assert!(is_assignable("str", "typing.Any"));
+
+        assert!(!is_assignable("float", "int"));
+        assert!(!is_assignable("int | None", "int"));
+        assert!(!is_assignable("list[int]", "list[str]"));
+        assert!(!is_assignable("dict", "list[int]"));
+    }
+}
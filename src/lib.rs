@@ -1,26 +1,129 @@
 pub mod analysis_error;
+pub mod baseline;
+pub mod builtins;
 pub mod check;
+pub mod config;
 pub mod files;
+pub mod fixes;
+pub mod fixture_index;
 pub mod functions;
+pub mod graph;
+pub mod hypothesis;
+pub mod imports;
+pub mod inference;
+pub mod listing;
+pub mod location;
+pub mod mocks;
 pub mod nodes;
-use std::path::PathBuf;
+pub mod plugins;
+pub mod render;
+pub mod rules;
+pub mod settings;
+pub mod suggestions;
+pub mod suppressions;
+pub mod usages;
+use std::{collections::HashSet, path::PathBuf};
 
-use check::check_file;
+use check::{check_file, check_unused_fixtures};
+use fixture_index::{get_conftest_files, FixtureIndex};
+use plugins::load_referenced_modules;
+use rules::Rule;
+use settings::Settings;
+use suppressions::{apply_suppressions, check_unused_suppressions};
+use usages::get_requested_fixtures;
 
-use crate::files::{parsed_python_file::ParsedPythonFile, python_file::PythonFile, read_file};
+use crate::files::{load_file, parsed_python_file::ParsedPythonFile};
 
-pub fn check_and_parse_file<'a, I: Iterator>(files: I) -> Vec<ParsedPythonFile>
+pub fn check_and_parse_file<'a, I>(files: I, settings: &Settings) -> Vec<ParsedPythonFile>
 where
     I: Iterator<Item = &'a PathBuf>,
 {
-    files
-        .map(read_file)
-        .map(PythonFile::parse)
-        .map(|f| {
-            let errors = check_file(&f);
-            ParsedPythonFile { errors, ..f }
-        })
-        .collect()
+    check_and_index_files(files, settings).0
+}
+
+/// Checks the files like [`check_and_parse_file`], also returning the index of the fixtures
+/// visible from them.
+pub fn check_and_index_files<'a, I>(
+    files: I,
+    settings: &Settings,
+) -> (Vec<ParsedPythonFile>, FixtureIndex)
+where
+    I: Iterator<Item = &'a PathBuf>,
+{
+    let (mut parsed, index) = load_and_index_files(files, settings);
+
+    for file in parsed.iter_mut() {
+        let errors = check_file(file, &index, settings);
+        file.errors.extend(errors);
+        file.errors
+            .retain(|err| settings.reports(err, &file.file.filename));
+        apply_suppressions(file);
+    }
+
+    (parsed, index)
+}
+
+/// Parses the files and indexes the fixtures visible from them, found in their conftest files and
+/// in the modules they reference, without checking them.
+pub fn load_and_index_files<'a, I>(
+    files: I,
+    settings: &Settings,
+) -> (Vec<ParsedPythonFile>, FixtureIndex)
+where
+    I: Iterator<Item = &'a PathBuf>,
+{
+    let parsed: Vec<ParsedPythonFile> = files.map(|f| load_file(f)).collect();
+
+    let paths: Vec<PathBuf> = parsed
+        .iter()
+        .map(|f| PathBuf::from(&f.file.filename))
+        .collect();
+    let conftests: Vec<ParsedPythonFile> = get_conftest_files(&paths)
+        .iter()
+        .map(|f| load_file(f))
+        .collect();
+
+    let loaded: Vec<&ParsedPythonFile> = parsed.iter().chain(conftests.iter()).collect();
+    let modules = load_referenced_modules(&loaded, &settings.source_roots);
+
+    let mut index = FixtureIndex::new(
+        loaded.iter().copied().chain(modules.iter()),
+        &settings.source_roots,
+    );
+    if settings.infer_types {
+        index.infer_types(loaded.into_iter().chain(modules.iter()));
+    }
+
+    (parsed, index)
+}
+
+/// Runs the checks that need to see every file at once, such as unused fixture detection. Unused
+/// suppression comments are reported last, once every other diagnostic is known.
+pub fn check_project(files: &mut [ParsedPythonFile], settings: &Settings) {
+    if settings.is_rule_active(Rule::UnusedFixture) {
+        let requested: HashSet<String> = files.iter().flat_map(get_requested_fixtures).collect();
+
+        for file in files.iter_mut() {
+            let errors = check_unused_fixtures(file, &requested, settings);
+            file.errors.extend(
+                errors
+                    .into_iter()
+                    .filter(|err| settings.reports(err, &file.file.filename)),
+            );
+            apply_suppressions(file);
+        }
+    }
+
+    if settings.is_rule_active(Rule::UnusedSuppression) {
+        for file in files.iter_mut().filter(|f| !f.suppressions.skip_file) {
+            let errors = check_unused_suppressions(file);
+            file.errors.extend(
+                errors
+                    .into_iter()
+                    .filter(|err| settings.reports(err, &file.file.filename)),
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -29,7 +132,19 @@ mod tests {
     use std::{collections::HashSet, path::PathBuf};
     use test_case::test_case;
 
-    use crate::{analysis_error::AnalysisError, check_and_parse_file};
+    use crate::{
+        analysis_error::AnalysisError, check_and_parse_file, check_project, location::Span,
+        rules::RuleSelection, settings::Settings, suggestions::FixtureSuggestion,
+    };
+
+    fn span(line: usize, start_column: usize, end_column: usize, function_line: usize) -> Span {
+        Span {
+            line,
+            start_column,
+            end_column,
+            function_line,
+        }
+    }
 
     fn get_errors_for_file(filepath: &str) -> Vec<AnalysisError> {
         match filepath {
@@ -39,39 +154,223 @@ mod tests {
                     argument_name: "sample_nested_list".to_string(),
                     expected_type: "List[List[int]]".to_string(),
                     provided_type: "List[List]".to_string(),
+                    span: span(29, 38, 48, 29),
                 },
                 AnalysisError::IncorrectArgumentType {
                     function_name: "test_hello_5".to_string(),
                     argument_name: "sample_nested_dict".to_string(),
                     expected_type: "List[List[Dict[int, str]]]".to_string(),
                     provided_type: "Dict".to_string(),
+                    span: span(25, 38, 42, 25),
                 },
             ],
             "./python-examples/test_sample.py" => vec![
                 AnalysisError::FixtureMissingReturnType {
                     fixture_name: "sample_missing_return_type".to_owned(),
+                    suggested_type: Some("int".to_owned()),
                 },
                 AnalysisError::MissingArgumentType {
                     function_name: "test_hello".to_owned(),
                     argument_name: "sample_string_2".to_owned(),
+                    span: span(24, 16, 31, 24),
                 },
                 AnalysisError::MissingArgumentType {
                     function_name: "sample_string_2".to_owned(),
                     argument_name: "sample_string".to_owned(),
+                    span: span(10, 21, 34, 10),
                 },
                 AnalysisError::IncorrectArgumentType {
                     function_name: "sample_string_3".to_owned(),
                     argument_name: "sample_string".to_owned(),
                     expected_type: "str".to_owned(),
                     provided_type: "int".to_owned(),
+                    span: span(15, 36, 39, 15),
                 },
                 AnalysisError::IncorrectArgumentType {
                     function_name: "test_hello".to_owned(),
                     argument_name: "sample_string".to_owned(),
                     expected_type: "str".to_owned(),
                     provided_type: "int".to_owned(),
+                    span: span(24, 48, 51, 24),
+                },
+            ],
+            "./python-examples/test_usefixtures.py" => vec![
+                AnalysisError::FixtureDoesNotExist {
+                    function_name: "test_function".to_owned(),
+                    argument_name: "databse".to_owned(),
+                    suggestions: vec![FixtureSuggestion {
+                        name: "database".to_owned(),
+                        location: Some("./python-examples/test_usefixtures.py:7".to_owned()),
+                    }],
+                    span: span(11, 2, 36, 12),
+                },
+                AnalysisError::FixtureDoesNotExist {
+                    function_name: "TestClass".to_owned(),
+                    argument_name: "cleanup_env".to_owned(),
+                    suggestions: vec![],
+                    span: span(16, 2, 40, 17),
+                },
+                AnalysisError::FixtureDoesNotExist {
+                    function_name: "TestClass".to_owned(),
+                    argument_name: "missing_in_class".to_owned(),
+                    suggestions: vec![],
+                    span: span(18, 18, 61, 18),
+                },
+                AnalysisError::FixtureDoesNotExist {
+                    function_name: "test_method".to_owned(),
+                    argument_name: "missing_in_method".to_owned(),
+                    suggestions: vec![],
+                    span: span(20, 6, 62, 21),
+                },
+            ],
+            "./python-examples/test_getfixturevalue.py" => vec![
+                AnalysisError::IncorrectFixtureValueType {
+                    function_name: "test_lookup".to_owned(),
+                    variable_name: "count".to_owned(),
+                    fixture_name: "user_name".to_owned(),
+                    expected_type: "str".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(12, 12, 15, 10),
+                },
+                AnalysisError::FixtureDoesNotExist {
+                    function_name: "test_lookup".to_owned(),
+                    argument_name: "user_nme".to_owned(),
+                    suggestions: vec![FixtureSuggestion {
+                        name: "user_name".to_owned(),
+                        location: Some("./python-examples/test_getfixturevalue.py:6".to_owned()),
+                    }],
+                    span: span(13, 15, 50, 10),
+                },
+            ],
+            "./python-examples/test_fixture_calls.py" => vec![
+                AnalysisError::FixtureCalledDirectly {
+                    function_name: "admin".to_owned(),
+                    fixture_name: "make_user".to_owned(),
+                    span: span(11, 12, 21, 10),
+                },
+                AnalysisError::FixtureCalledDirectly {
+                    function_name: "test_direct_call".to_owned(),
+                    fixture_name: "make_user".to_owned(),
+                    span: span(15, 22, 31, 14),
+                },
+            ],
+            "./python-examples/test_shadowed_fixture_calls.py" => {
+                vec![AnalysisError::FixtureCalledDirectly {
+                    function_name: "test_binding_in_nested_scope".to_owned(),
+                    fixture_name: "admin".to_owned(),
+                    span: span(40, 25, 30, 35),
+                }]
+            }
+            "./python-examples/overrides/test_overrides.py" => vec![
+                AnalysisError::DuplicateFixture {
+                    fixture_name: "token".to_owned(),
+                    first_line: 15,
+                    second_line: 20,
+                },
+                AnalysisError::IncompatibleFixtureOverride {
+                    fixture_name: "user_id".to_owned(),
+                    line: 10,
+                    fixture_type: "str".to_owned(),
+                    overridden_type: "int".to_owned(),
+                    overridden_location: "./python-examples/overrides/conftest.py:10".to_owned(),
+                },
+            ],
+            "./python-examples/test_arguments.py" => vec![
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_keyword_only".to_owned(),
+                    argument_name: "user_name".to_owned(),
+                    expected_type: "str".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(16, 37, 40, 16),
+                },
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_method".to_owned(),
+                    argument_name: "user_name".to_owned(),
+                    expected_type: "str".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(30, 38, 41, 30),
+                },
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_partial".to_owned(),
+                    argument_name: "user_name".to_owned(),
+                    expected_type: "str".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(38, 36, 39, 38),
                 },
             ],
+            "./python-examples/test_mocks.py" => vec![],
+            "./python-examples/test_hypothesis.py" => vec![
+                AnalysisError::IncorrectStrategyType {
+                    function_name: "test_positional".to_owned(),
+                    argument_name: "flag".to_owned(),
+                    expected_type: "bool".to_owned(),
+                    provided_type: "str".to_owned(),
+                    span: span(26, 55, 58, 26),
+                },
+                AnalysisError::IncorrectStrategyType {
+                    function_name: "test_builds".to_owned(),
+                    argument_name: "ratio".to_owned(),
+                    expected_type: "float".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(31, 36, 39, 31),
+                },
+            ],
+            "./python-examples/qualified/test_qualified.py" => {
+                vec![AnalysisError::IncorrectArgumentType {
+                    function_name: "test_config".to_owned(),
+                    argument_name: "config".to_owned(),
+                    expected_type: "configparser.ConfigParser".to_owned(),
+                    provided_type: "test_qualified.Config".to_owned(),
+                    span: span(21, 25, 31, 21),
+                }]
+            }
+            "./python-examples/test_forward_refs.py" => {
+                vec![AnalysisError::IncorrectArgumentType {
+                    function_name: "test_user_id".to_owned(),
+                    argument_name: "user".to_owned(),
+                    expected_type: "User".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(26, 24, 29, 26),
+                }]
+            }
+            "./python-examples/test_annotations.py" => vec![
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_mode".to_owned(),
+                    argument_name: "mode".to_owned(),
+                    expected_type: "Literal['r', 'w']".to_owned(),
+                    provided_type: "Literal['r']".to_owned(),
+                    span: span(43, 21, 33, 43),
+                },
+                AnalysisError::UnsupportedAnnotation {
+                    function_name: "factory".to_owned(),
+                    annotation: "make_type()".to_owned(),
+                    line: 31,
+                    span: span(31, 18, 29, 31),
+                },
+                AnalysisError::UnsupportedAnnotation {
+                    function_name: "test_factory".to_owned(),
+                    annotation: "\"List[\"".to_owned(),
+                    line: 39,
+                    span: span(39, 27, 34, 39),
+                },
+            ],
+            "./python-examples/test_generators.py" => vec![
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_generator_types".to_owned(),
+                    argument_name: "user".to_owned(),
+                    expected_type: "User".to_owned(),
+                    provided_type: "Iterator[User]".to_owned(),
+                    span: span(34, 32, 46, 34),
+                },
+                AnalysisError::IncorrectArgumentType {
+                    function_name: "test_generator_types".to_owned(),
+                    argument_name: "session".to_owned(),
+                    expected_type: "str".to_owned(),
+                    provided_type: "int".to_owned(),
+                    span: span(34, 57, 60, 34),
+                },
+            ],
+            "./python-examples/type_checking/test_type_checking.py" => vec![],
             "./python-examples/folder/test_empty.py" => vec![],
             _ => panic!("Invalid filename."),
         }
@@ -79,10 +378,23 @@ mod tests {
 
     #[test_case( "./python-examples/test_sample_complex.py" ; "for ./python-examples/test_sample_complex.py")]
     #[test_case( "./python-examples/test_sample.py" ; "for ./python-examples/test_sample.py")]
+    #[test_case( "./python-examples/test_usefixtures.py" ; "for ./python-examples/test_usefixtures.py")]
+    #[test_case( "./python-examples/test_getfixturevalue.py" ; "for ./python-examples/test_getfixturevalue.py")]
+    #[test_case( "./python-examples/test_fixture_calls.py" ; "for ./python-examples/test_fixture_calls.py")]
+    #[test_case( "./python-examples/test_shadowed_fixture_calls.py" ; "for ./python-examples/test_shadowed_fixture_calls.py")]
+    #[test_case( "./python-examples/overrides/test_overrides.py" ; "for ./python-examples/overrides/test_overrides.py")]
+    #[test_case( "./python-examples/test_arguments.py" ; "for ./python-examples/test_arguments.py")]
+    #[test_case( "./python-examples/test_mocks.py" ; "for ./python-examples/test_mocks.py")]
+    #[test_case( "./python-examples/test_hypothesis.py" ; "for ./python-examples/test_hypothesis.py")]
+    #[test_case( "./python-examples/qualified/test_qualified.py" ; "for ./python-examples/qualified/test_qualified.py")]
+    #[test_case( "./python-examples/test_forward_refs.py" ; "for ./python-examples/test_forward_refs.py")]
+    #[test_case( "./python-examples/test_annotations.py" ; "for ./python-examples/test_annotations.py")]
+    #[test_case( "./python-examples/test_generators.py" ; "for ./python-examples/test_generators.py")]
+    #[test_case( "./python-examples/type_checking/test_type_checking.py" ; "for ./python-examples/type_checking/test_type_checking.py")]
     #[test_case( "./python-examples/folder/test_empty.py" ; "for ./python-examples/folder/test_empty.py")]
     fn assert_check_file(filepath: &str) {
         let path = PathBuf::from(filepath);
-        let files = check_and_parse_file([path].iter());
+        let files = check_and_parse_file([path].iter(), &Settings::default());
         let expected_value = get_errors_for_file(filepath);
 
         let provided_set: HashSet<&AnalysisError> = HashSet::from_iter(files[0].errors.iter());
@@ -90,6 +402,208 @@ mod tests {
 
         assert_eq!(provided_set, expected_set)
     }
+
+    #[test]
+    fn assert_check_mock_annotations() {
+        let settings = Settings {
+            check_mock_annotations: true,
+            ..Settings::default()
+        };
+        let files = check_and_parse_file(
+            [PathBuf::from("./python-examples/test_mocks.py")].iter(),
+            &settings,
+        );
+
+        let provided_set: HashSet<&AnalysisError> = HashSet::from_iter(files[0].errors.iter());
+        let expected_value = [
+            AnalysisError::IncorrectMockArgumentType {
+                function_name: "test_unannotated_mock".to_owned(),
+                argument_name: "getcwd".to_owned(),
+                span: span(30, 27, 33, 30),
+            },
+            AnalysisError::IncorrectMockArgumentType {
+                function_name: "test_wrong_mock_type".to_owned(),
+                argument_name: "getcwd".to_owned(),
+                span: span(35, 34, 37, 35),
+            },
+        ];
+        let expected_set: HashSet<&AnalysisError> = HashSet::from_iter(expected_value.iter());
+
+        assert_eq!(provided_set, expected_set)
+    }
+
+    #[test]
+    fn assert_check_plugins_and_imported_fixtures() {
+        let settings = Settings {
+            source_roots: vec![PathBuf::from("./python-examples")],
+            ..Settings::default()
+        };
+        let files = check_and_parse_file(
+            [PathBuf::from("./python-examples/plugins/test_plugins.py")].iter(),
+            &settings,
+        );
+
+        let provided_set: HashSet<&AnalysisError> = HashSet::from_iter(files[0].errors.iter());
+        let expected_value = [
+            AnalysisError::IncorrectArgumentType {
+                function_name: "test_plugins".to_owned(),
+                argument_name: "connection".to_owned(),
+                expected_type: "int".to_owned(),
+                provided_type: "str".to_owned(),
+                span: span(6, 45, 48, 6),
+            },
+            AnalysisError::FixtureDoesNotExist {
+                function_name: "test_plugins".to_owned(),
+                argument_name: "missing".to_owned(),
+                suggestions: vec![],
+                span: span(6, 92, 95, 6),
+            },
+        ];
+        let expected_set: HashSet<&AnalysisError> = HashSet::from_iter(expected_value.iter());
+
+        assert_eq!(provided_set, expected_set)
+    }
+
+    #[test]
+    fn assert_check_inferred_types() {
+        let settings = Settings {
+            infer_types: true,
+            ..Settings::default()
+        };
+        let files = check_and_parse_file(
+            [PathBuf::from("./python-examples/inferred/test_inferred.py")].iter(),
+            &settings,
+        );
+
+        let provided_set: HashSet<&AnalysisError> = HashSet::from_iter(
+            files[0]
+                .errors
+                .iter()
+                .filter(|e| !matches!(e, AnalysisError::FixtureMissingReturnType { .. })),
+        );
+        let expected_value = [
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name: "test_mismatching".to_owned(),
+                argument_name: "numbers".to_owned(),
+                inferred_type: "list[int]".to_owned(),
+                provided_type: "list[str]".to_owned(),
+                span: span(45, 31, 40, 45),
+            },
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name: "test_mismatching".to_owned(),
+                argument_name: "greeting".to_owned(),
+                inferred_type: "str".to_owned(),
+                provided_type: "bytes".to_owned(),
+                span: span(45, 52, 57, 45),
+            },
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name: "test_mismatching".to_owned(),
+                argument_name: "port".to_owned(),
+                inferred_type: "int".to_owned(),
+                provided_type: "str".to_owned(),
+                span: span(45, 65, 68, 45),
+            },
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name: "test_incompatible".to_owned(),
+                argument_name: "maybe_number".to_owned(),
+                inferred_type: "int | None".to_owned(),
+                provided_type: "int".to_owned(),
+                span: span(62, 37, 40, 62),
+            },
+            AnalysisError::IncorrectInferredArgumentType {
+                function_name: "test_incompatible".to_owned(),
+                argument_name: "ratio".to_owned(),
+                inferred_type: "int".to_owned(),
+                provided_type: "str".to_owned(),
+                span: span(62, 49, 52, 62),
+            },
+        ];
+        let expected_set: HashSet<&AnalysisError> = HashSet::from_iter(expected_value.iter());
+
+        assert_eq!(provided_set, expected_set)
+    }
+
+    #[test]
+    fn assert_check_project_suppressions() {
+        let settings = Settings {
+            unused_fixtures: true,
+            rules: RuleSelection {
+                extend_select: vec!["PTC015".to_owned()],
+                ..RuleSelection::default()
+            },
+            ..Settings::default()
+        };
+        let paths = [
+            PathBuf::from("./python-examples/suppressions/test_suppressions.py"),
+            PathBuf::from("./python-examples/suppressions/test_skipped.py"),
+            PathBuf::from("./python-examples/suppressions/test_same_name.py"),
+        ];
+        let mut files = check_and_parse_file(paths.iter(), &settings);
+        check_project(&mut files, &settings);
+
+        let provided_set: HashSet<&AnalysisError> = HashSet::from_iter(files[0].errors.iter());
+        let expected_value = [
+            AnalysisError::IncorrectArgumentType {
+                function_name: "test_wrong_code".to_owned(),
+                argument_name: "user".to_owned(),
+                expected_type: "str".to_owned(),
+                provided_type: "int".to_owned(),
+                span: span(25, 27, 30, 25),
+            },
+            AnalysisError::UnusedSuppression {
+                line: 25,
+                codes: vec!["PTC003".to_owned()],
+            },
+            AnalysisError::UnusedSuppression {
+                line: 29,
+                codes: vec![],
+            },
+        ];
+        let expected_set: HashSet<&AnalysisError> = HashSet::from_iter(expected_value.iter());
+
+        assert_eq!(provided_set, expected_set);
+        assert_eq!(files[1].errors, vec![]);
+        // The comment on the first method does not silence the second one, which shares its name.
+        assert_eq!(
+            files[2].errors,
+            vec![AnalysisError::IncorrectArgumentType {
+                function_name: "test_create".to_owned(),
+                argument_name: "user".to_owned(),
+                expected_type: "str".to_owned(),
+                provided_type: "int".to_owned(),
+                span: span(15, 33, 36, 15),
+            }]
+        );
+    }
+
+    #[test]
+    fn assert_check_project_unused_fixtures() {
+        let paths = [
+            PathBuf::from("./python-examples/unused/conftest.py"),
+            PathBuf::from("./python-examples/unused/test_unused.py"),
+        ];
+        let settings = Settings {
+            unused_fixtures: true,
+            unused_fixtures_allowlist: vec!["legacy_fixture".to_owned()],
+            ..Settings::default()
+        };
+        let mut files = check_and_parse_file(paths.iter(), &settings);
+        check_project(&mut files, &settings);
+
+        let unused: Vec<&AnalysisError> = files
+            .iter()
+            .flat_map(|f| f.errors.iter())
+            .filter(|e| matches!(e, AnalysisError::UnusedFixture { .. }))
+            .collect();
+
+        assert_eq!(
+            unused,
+            vec![&AnalysisError::UnusedFixture {
+                fixture_name: "unused_client".to_owned(),
+                line: 10,
+            }]
+        )
+    }
 }
 
 #[cfg(test)]
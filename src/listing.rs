@@ -0,0 +1,158 @@
+use serde::Serialize;
+
+use crate::{
+    files::parsed_python_file::ParsedPythonFile,
+    fixture_index::{FixtureDefinition, FixtureIndex, FixtureSource},
+    imports::ResolvedAnnotation,
+    nodes::FixtureUtil,
+};
+
+/// A fixture visible from a file, as listed by the `fixtures` subcommand.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListedFixture {
+    pub name: String,
+    /// The type of the value requesting functions receive, as written in the fixture's module.
+    pub return_type: Option<String>,
+    pub qualified_type: Option<String>,
+    /// Whether the type is inferred from the fixture's body rather than annotated.
+    pub inferred: bool,
+    pub scope: String,
+    pub autouse: bool,
+    pub filename: String,
+    pub line: usize,
+    pub source: FixtureSource,
+}
+
+/// The fixtures visible from a file, by decreasing precedence of their source, then by location.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FixtureListing {
+    pub filename: String,
+    pub fixtures: Vec<ListedFixture>,
+}
+
+/// Returns the type of the value a fixture provides, and whether it is inferred from its body
+/// because the fixture has no return annotation.
+pub fn get_fixture_type(
+    fixture: &FixtureDefinition,
+    index: &FixtureIndex,
+) -> Option<(ResolvedAnnotation, bool)> {
+    match fixture.return_annotation() {
+        Some(annotation) => Some((annotation, false)),
+        None => Some((index.inferred_type(fixture)?.clone(), true)),
+    }
+}
+
+/// Lists the fixtures visible from `file`. A fixture overridden by another one is not listed.
+pub fn list_fixtures(file: &ParsedPythonFile, index: &FixtureIndex) -> FixtureListing {
+    let mut fixtures: Vec<ListedFixture> = index
+        .visible_fixture_sources(file)
+        .into_iter()
+        .map(|(name, (source, fixture))| {
+            let (annotation, inferred) = match get_fixture_type(&fixture, index) {
+                Some((annotation, inferred)) => (Some(annotation), inferred),
+                None => (None, false),
+            };
+
+            ListedFixture {
+                name,
+                inferred,
+                return_type: annotation.as_ref().map(|a| a.written.clone()),
+                qualified_type: annotation.map(|a| a.qualified),
+                scope: fixture.scope(),
+                autouse: fixture.function.is_autouse_fixture(),
+                filename: fixture.filename,
+                line: fixture.line,
+                source,
+            }
+        })
+        .collect();
+
+    fixtures.sort_by(|a, b| {
+        (a.source, &a.filename, a.line, &a.name).cmp(&(b.source, &b.filename, b.line, &b.name))
+    });
+
+    FixtureListing {
+        filename: file.file.filename.clone(),
+        fixtures,
+    }
+}
+
+/// Renders the fixtures visible from each file as a JSON array.
+pub fn render_listings_json(listings: &[FixtureListing]) -> String {
+    // Listings only hold strings, numbers and booleans, which always serialize.
+    serde_json::to_string_pretty(listings).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{fixture_index::FixtureSource, load_and_index_files, settings::Settings};
+
+    use super::{list_fixtures, render_listings_json};
+
+    #[test]
+    fn assert_list_fixtures() {
+        let settings = Settings {
+            infer_types: true,
+            ..Settings::default()
+        };
+        let files = [PathBuf::from(
+            "./python-examples/listing/sub/test_listing.py",
+        )];
+        let (parsed, index) = load_and_index_files(files.iter(), &settings);
+        let listing = list_fixtures(&parsed[0], &index);
+
+        let sources: Vec<(&str, FixtureSource)> = listing
+            .fixtures
+            .iter()
+            .map(|f| (f.name.as_str(), f.source))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("client", FixtureSource::Module),
+                ("database", FixtureSource::Conftest { layer: 0 }),
+                ("settings", FixtureSource::Conftest { layer: 1 }),
+            ]
+        );
+
+        let database = &listing.fixtures[1];
+        assert_eq!(database.return_type.as_deref(), Some("dict[str, int]"));
+        assert!(database.inferred);
+        assert_eq!(database.scope, "module");
+        assert!(!database.autouse);
+
+        let settings = &listing.fixtures[2];
+        assert_eq!(settings.return_type.as_deref(), Some("Path"));
+        assert!(!settings.inferred);
+        assert_eq!(settings.scope, "session");
+        assert!(settings.autouse);
+        assert_eq!(
+            listing.fixtures[2].qualified_type.as_deref(),
+            Some("pathlib.Path")
+        );
+        assert!(listing.fixtures[2]
+            .filename
+            .ends_with("python-examples/listing/conftest.py"));
+    }
+
+    #[test]
+    fn assert_render_listings_json() {
+        let files = [PathBuf::from(
+            "./python-examples/listing/sub/test_listing.py",
+        )];
+        let (parsed, index) = load_and_index_files(files.iter(), &Settings::default());
+        let listings = vec![list_fixtures(&parsed[0], &index)];
+        let rendered: serde_json::Value =
+            serde_json::from_str(&render_listings_json(&listings)).unwrap();
+
+        assert_eq!(rendered[0]["fixtures"][1]["name"], "database");
+        assert_eq!(
+            rendered[0]["fixtures"][1]["return_type"],
+            serde_json::Value::Null
+        );
+        assert_eq!(rendered[0]["fixtures"][1]["source"]["kind"], "conftest");
+        assert_eq!(rendered[0]["fixtures"][1]["source"]["layer"], 0);
+    }
+}
@@ -0,0 +1,218 @@
+use rustpython_ast::{ArgWithDefault, Ranged, StmtFunctionDef};
+use rustpython_parser::{lexer::lex_starts_at, Tok};
+use rustpython_parser_core::Mode;
+use rustpython_parser_vendored::text_size::{TextRange, TextSize};
+
+use crate::{
+    analysis_error::AnalysisError,
+    files::{parsed_python_file::ParsedPythonFile, python_file::PythonFile},
+    functions::get_functions,
+};
+
+/// A span within a single line of a file, along with the line's text, so that it can be rendered
+/// after the file's content is gone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLine {
+    pub filename: String,
+    pub line: usize,
+    pub text: String,
+    /// One-indexed column of the first character of the span.
+    pub start_column: usize,
+    /// One-indexed column following the last character of the span.
+    pub end_column: usize,
+}
+
+impl SourceLine {
+    /// Returns the span of `range`, cut at the end of its first line.
+    pub fn new(file: &PythonFile, range: TextRange) -> Self {
+        let line = file.line_number(range.start());
+        let text = file.line(line).unwrap_or_default().to_owned();
+        let start_column = file.column_number(range.start());
+        let end_column = if file.line_number(range.end()) == line {
+            file.column_number(range.end())
+        } else {
+            text.chars().count() + 1
+        };
+
+        SourceLine {
+            filename: file.filename.clone(),
+            line,
+            text,
+            start_column,
+            end_column: end_column.max(start_column + 1),
+        }
+    }
+
+    /// Returns the line a recorded span points at.
+    pub fn at(file: &PythonFile, span: &Span) -> Option<Self> {
+        Some(SourceLine {
+            filename: file.filename.clone(),
+            line: span.line,
+            text: file.line(span.line)?.to_owned(),
+            start_column: span.start_column,
+            end_column: span.end_column,
+        })
+    }
+}
+
+/// Where a diagnostic about a function points, recorded when the diagnostic is reported, as
+/// functions are not known by their name alone, e.g. test methods of different classes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: usize,
+    /// One-indexed column of the first character of the span.
+    pub start_column: usize,
+    /// One-indexed column following the last character of the span.
+    pub end_column: usize,
+    /// The line of the `def` statement of the function, or of the class or `pytestmark`
+    /// assignment a `usefixtures` mark applies to.
+    pub function_line: usize,
+}
+
+impl Span {
+    /// Returns the span of `range`, cut at the end of its first line, in the function, class or
+    /// `pytestmark` assignment starting at `owner_start`.
+    pub fn new(file: &PythonFile, range: TextRange, owner_start: TextSize) -> Self {
+        let source = SourceLine::new(file, range);
+        Span {
+            line: source.line,
+            start_column: source.start_column,
+            end_column: source.end_column,
+            function_line: file.line_number(owner_start),
+        }
+    }
+}
+
+/// Returns the checked function a span was recorded in.
+pub fn find_function<'a>(file: &'a ParsedPythonFile, span: &Span) -> Option<&'a StmtFunctionDef> {
+    file.fixtures
+        .values()
+        .chain(file.test_cases.values())
+        .find(|f| file.file.line_number(f.start()) == span.function_line)
+}
+
+pub fn find_argument<'a>(func: &'a StmtFunctionDef, name: &str) -> Option<&'a ArgWithDefault> {
+    func.args
+        .posonlyargs
+        .iter()
+        .chain(func.args.args.iter())
+        .chain(func.args.kwonlyargs.iter())
+        .find(|a| a.def.arg.as_str() == name)
+}
+
+/// Returns the span of a function's name, which is the name token following `def`.
+fn function_span(file: &ParsedPythonFile, func: &StmtFunctionDef) -> Option<SourceLine> {
+    let mut tokens = lex_starts_at(&file.file.content[func.range()], Mode::Module, func.start())
+        .map_while(Result::ok);
+    tokens.find(|(token, _)| matches!(token, Tok::Def))?;
+    let (_, range) = tokens.next()?;
+
+    Some(SourceLine::new(&file.file, range))
+}
+
+/// Returns the span of the name of the module-level function `name` whose `def` is on `line`.
+pub fn function_span_at(file: &ParsedPythonFile, name: &str, line: usize) -> Option<SourceLine> {
+    let func = get_functions(&file.body)
+        .into_iter()
+        .find(|f| f.name.as_str() == name && file.file.line_number(f.start()) == line)?;
+    function_span(file, &func)
+}
+
+/// Returns the span a diagnostic points at, which only depends on the file it was found in.
+pub fn primary_span(err: &AnalysisError, file: &ParsedPythonFile) -> Option<SourceLine> {
+    match err {
+        AnalysisError::IncorrectArgumentType { span, .. }
+        | AnalysisError::MissingArgumentType { span, .. }
+        | AnalysisError::IncorrectInferredArgumentType { span, .. }
+        | AnalysisError::FixtureDoesNotExist { span, .. }
+        | AnalysisError::IncorrectFixtureValueType { span, .. }
+        | AnalysisError::FixtureCalledDirectly { span, .. }
+        | AnalysisError::IncorrectStrategyType { span, .. }
+        | AnalysisError::IncorrectMockArgumentType { span, .. }
+        | AnalysisError::UnsupportedAnnotation { span, .. } => SourceLine::at(&file.file, span),
+        AnalysisError::FixtureMissingReturnType { fixture_name, .. } => {
+            function_span(file, file.fixtures.get(fixture_name)?)
+        }
+        AnalysisError::DuplicateFixture {
+            fixture_name,
+            second_line,
+            ..
+        } => function_span_at(file, fixture_name, *second_line),
+        AnalysisError::IncompatibleFixtureOverride { fixture_name, .. } => {
+            let func = file.fixtures.get(fixture_name)?;
+            match func.returns.as_deref() {
+                Some(returns) => Some(SourceLine::new(&file.file, returns.range())),
+                None => function_span(file, func),
+            }
+        }
+        AnalysisError::UnusedFixture { fixture_name, .. } => {
+            function_span(file, file.fixtures.get(fixture_name)?)
+        }
+        AnalysisError::UnparsableFile { line, column, .. } => Some(SourceLine {
+            filename: file.file.filename.clone(),
+            line: *line,
+            text: file.file.line(*line)?.to_owned(),
+            start_column: *column,
+            end_column: column + 1,
+        }),
+        AnalysisError::UnusedSuppression { line, .. } => file
+            .suppressions
+            .comments
+            .iter()
+            .find(|suppression| suppression.line == *line)
+            .map(|suppression| SourceLine::new(&file.file, suppression.range)),
+        AnalysisError::UnreadableFile { .. } => None,
+    }
+}
+
+/// Returns the line of the `def` statement of the function a diagnostic is about.
+pub fn function_line(err: &AnalysisError, file: &ParsedPythonFile) -> Option<usize> {
+    match err {
+        AnalysisError::IncorrectArgumentType { span, .. }
+        | AnalysisError::MissingArgumentType { span, .. }
+        | AnalysisError::IncorrectInferredArgumentType { span, .. }
+        | AnalysisError::FixtureDoesNotExist { span, .. }
+        | AnalysisError::IncorrectFixtureValueType { span, .. }
+        | AnalysisError::FixtureCalledDirectly { span, .. }
+        | AnalysisError::IncorrectStrategyType { span, .. }
+        | AnalysisError::IncorrectMockArgumentType { span, .. }
+        | AnalysisError::UnsupportedAnnotation { span, .. } => Some(span.function_line),
+        AnalysisError::FixtureMissingReturnType { fixture_name, .. } => file
+            .fixtures
+            .get(fixture_name)
+            .map(|func| file.file.line_number(func.start())),
+        AnalysisError::DuplicateFixture { second_line, .. } => Some(*second_line),
+        AnalysisError::IncompatibleFixtureOverride { line, .. }
+        | AnalysisError::UnusedFixture { line, .. }
+        | AnalysisError::UnusedSuppression { line, .. } => Some(*line),
+        AnalysisError::UnparsableFile { .. } | AnalysisError::UnreadableFile { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{check_and_index_files, settings::Settings};
+
+    use super::primary_span;
+
+    #[test]
+    fn assert_primary_span() {
+        let files = [
+            PathBuf::from("./python-examples/test_fixture_calls.py"),
+            PathBuf::from("./python-examples/overrides/test_overrides.py"),
+        ];
+        let (parsed, _) = check_and_index_files(files.iter(), &Settings::default());
+        let spans: Vec<(usize, usize, usize)> = parsed
+            .iter()
+            .flat_map(|file| file.errors.iter().filter_map(|err| primary_span(err, file)))
+            .map(|span| (span.line, span.start_column, span.end_column))
+            .collect();
+
+        // The called fixture, not the variable starting with its name.
+        assert!(spans.contains(&(15, 22, 31)));
+        // The redefined fixture's name, without the parenthesis following it.
+        assert!(spans.contains(&(20, 5, 10)));
+    }
+}
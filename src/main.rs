@@ -1,23 +1,225 @@
 //! Print the AST for a given Python file.
 #![allow(clippy::print_stdout, clippy::print_stderr)]
 
-use clap::Parser;
+use clap::{Args as ClapArgs, CommandFactory, Parser, Subcommand, ValueEnum};
 use indicatif::{ProgressBar, ProgressIterator, ProgressState, ProgressStyle};
-use std::{fmt::Write, path::PathBuf};
+use std::{
+    ffi::OsString,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use anyhow::Result;
-use pytest_typechecker::{check_and_parse_file, files::get_files_list};
+use prettytable::{format, row, Table};
+use pytest_typechecker::{
+    analysis_error::Severity,
+    baseline::Baseline,
+    check_and_index_files, check_project,
+    config::Config,
+    files::get_files_list,
+    fixes::{fix_file, FixedFile},
+    fixture_index::is_conftest,
+    graph::FixtureGraph,
+    listing::{list_fixtures, render_listings_json, FixtureListing},
+    load_and_index_files,
+    render::{render_json, render_rich},
+    rules::{clean_selectors, get_unknown_selectors, Rule},
+    settings::Settings,
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    /// One line per diagnostic.
+    Concise,
+    /// Diagnostics with the offending source lines, related definitions and suggestions.
+    Rich,
+    /// A JSON array of diagnostics, for editors and other tools.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ListFormat {
+    /// A table per file.
+    Table,
+    /// A JSON array with the fixtures of each file, for editors and other tools.
+    Json,
+}
+
+#[derive(ClapArgs, Debug)]
+struct FixturesArgs {
+    /// Python pytest file or directory whose test files' visible fixtures are listed.
+    #[arg(required = true, num_args(1..))]
+    path: Vec<PathBuf>,
+
+    /// How the fixtures are printed.
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    format: ListFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GraphFormat {
+    /// Graphviz DOT, e.g. for `dot -Tsvg`.
+    Dot,
+    /// A Mermaid flowchart, e.g. for Markdown documents.
+    Mermaid,
+}
+
+#[derive(ClapArgs, Debug)]
+struct GraphArgs {
+    /// Python pytest file or directory whose tests and fixtures are part of the graph.
+    #[arg(required = true, num_args(1..))]
+    path: Vec<PathBuf>,
+
+    /// How the graph is printed.
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+
+    /// Only keep the test or fixture with this name and the fixtures it depends on, directly or
+    /// not.
+    #[arg(long, value_name = "NAME")]
+    filter: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the fixtures visible from each test file, with their type, scope and definition,
+    /// without running pytest.
+    Fixtures(FixturesArgs),
+    /// Print the dependencies of tests and fixtures on fixtures as a graph.
+    Graph(GraphArgs),
+}
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Python pytest file or directory for which to check fixture types.
-    #[arg(required = true, num_args(1..))]
+    #[arg(required_unless_present = "show_rules", num_args(1..))]
     file: Vec<PathBuf>,
 
     /// Check files recursively.
-    #[arg(required = false, short, long, default_value_t = false)]
+    #[arg(required = false, short, long, global = true, default_value_t = false)]
     recursive: bool,
+
+    /// Report fixtures that are never requested by any checked file.
+    #[arg(long, default_value_t = false)]
+    unused_fixtures: bool,
+
+    /// Fixture that should never be reported as unused. Can be repeated.
+    #[arg(long = "allow-unused", value_name = "FIXTURE")]
+    allow_unused: Vec<String>,
+
+    /// Require arguments injected by mock.patch decorators to be annotated as Mock, MagicMock or
+    /// AsyncMock.
+    #[arg(long, default_value_t = false)]
+    check_mock_annotations: bool,
+
+    /// Directory against which modules from pytest_plugins and imports are resolved. Can be
+    /// repeated. Defaults to the current directory.
+    #[arg(long = "source-root", value_name = "DIR", global = true)]
+    source_roots: Vec<PathBuf>,
+
+    /// Check arguments receiving a fixture without a return annotation against the type inferred
+    /// from the fixture's body.
+    #[arg(long, default_value_t = false)]
+    infer: bool,
+
+    /// How diagnostics are printed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Concise)]
+    output_format: OutputFormat,
+
+    /// Comma-separated rule codes, code prefixes or names to report, replacing the default rules.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    select: Option<Vec<String>>,
+
+    /// Comma-separated rules to report on top of the selected ones.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    extend_select: Vec<String>,
+
+    /// Comma-separated rules that are never reported.
+    #[arg(long, value_name = "RULE", value_delimiter = ',')]
+    ignore: Vec<String>,
+
+    /// pyproject.toml file holding the [tool.pytest-typechecker] configuration.
+    #[arg(long, value_name = "FILE", default_value = "pyproject.toml")]
+    config: PathBuf,
+
+    /// Only report the diagnostics missing from this baseline file.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Record the current diagnostics in a baseline file, then exit.
+    #[arg(long, value_name = "FILE")]
+    write_baseline: Option<PathBuf>,
+
+    /// Apply the fixes of the fixable diagnostics, then report the remaining ones.
+    #[arg(long, default_value_t = false)]
+    fix: bool,
+
+    /// Print the changes the fixes would make as a diff, without writing them.
+    #[arg(long, default_value_t = false)]
+    diff: bool,
+
+    /// Also apply the fixes that may change the meaning of the code, such as replacing an argument
+    /// annotation with the type of its fixture.
+    #[arg(long, default_value_t = false)]
+    unsafe_fixes: bool,
+
+    /// Print every rule with whether it is active, then exit.
+    #[arg(long, default_value_t = false)]
+    show_rules: bool,
+}
+
+fn print_rules(settings: &Settings) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.set_titles(row!["Code", "Name", "Active"]);
+
+    for rule in Rule::ALL {
+        let active = if settings.is_rule_active(rule) {
+            "yes"
+        } else {
+            "no"
+        };
+        table.add_row(row![rule.code(), rule.name(), active]);
+    }
+
+    table.printstd();
+}
+
+fn print_fixtures(listings: &[FixtureListing]) {
+    for listing in listings {
+        println!("{}", listing.filename);
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+        table.set_titles(row![
+            "Fixture", "Type", "Scope", "Autouse", "Location", "Source"
+        ]);
+
+        for fixture in listing.fixtures.iter() {
+            let return_type = match (&fixture.return_type, fixture.inferred) {
+                (Some(return_type), true) => format!("{} (inferred)", return_type),
+                (Some(return_type), false) => return_type.clone(),
+                (None, _) => "-".to_owned(),
+            };
+            let autouse = if fixture.autouse { "yes" } else { "no" };
+            table.add_row(row![
+                fixture.name,
+                return_type,
+                fixture.scope,
+                autouse,
+                format!("{}:{}", fixture.filename, fixture.line),
+                fixture.source
+            ]);
+        }
+
+        table.printstd();
+        println!();
+    }
 }
 
 fn get_progress_bar(total_len: u64) -> ProgressBar {
@@ -37,15 +239,190 @@ fn get_progress_bar(total_len: u64) -> ProgressBar {
     pb
 }
 
-fn main() -> Result<(), String> {
-    let args = Args::parse();
+/// Makes a first argument naming both a subcommand and an existing file or directory of
+/// `current_dir` a path, so that e.g. a `fixtures` directory can still be checked.
+fn resolve_subcommand_paths(mut args: Vec<OsString>, current_dir: &Path) -> Vec<OsString> {
+    if let Some(first) = args.get_mut(1) {
+        let is_subcommand = Args::command()
+            .get_subcommands()
+            .any(|command| first.to_str() == Some(command.get_name()));
+        if is_subcommand && current_dir.join(&*first).exists() {
+            *first = Path::new(".").join(&*first).into_os_string();
+        }
+    }
+    args
+}
+
+fn main() -> Result<ExitCode, String> {
+    let args = Args::parse_from(resolve_subcommand_paths(
+        std::env::args_os().collect(),
+        Path::new("."),
+    ));
+
+    let mut rules = Config::load(&args.config)?.into_rule_selection();
+    if let Some(select) = args.select {
+        rules.select = Some(clean_selectors(select));
+    }
+    rules
+        .extend_select
+        .extend(clean_selectors(args.extend_select));
+    rules.ignore.extend(clean_selectors(args.ignore));
+
+    let unknown = get_unknown_selectors(rules.selectors());
+    if !unknown.is_empty() {
+        return Err(format!("Unknown rule selectors: {}.", unknown.join(", ")));
+    }
+
+    let settings = Settings {
+        unused_fixtures: args.unused_fixtures,
+        unused_fixtures_allowlist: args.allow_unused,
+        check_mock_annotations: args.check_mock_annotations,
+        source_roots: args.source_roots,
+        rules,
+        infer_types: args.infer,
+    };
+
+    if args.show_rules {
+        print_rules(&settings);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match args.command {
+        Some(Command::Fixtures(fixtures_args)) => {
+            let settings = Settings {
+                infer_types: true,
+                ..settings
+            };
+            let files: Vec<PathBuf> = get_files_list(&fixtures_args.path, args.recursive)?
+                .into_iter()
+                .filter(|f| !is_conftest(&f.to_string_lossy()))
+                .collect();
+            let (parsed_files, index) = load_and_index_files(files.iter(), &settings);
+            let listings: Vec<FixtureListing> = parsed_files
+                .iter()
+                .map(|file| list_fixtures(file, &index))
+                .collect();
+
+            match fixtures_args.format {
+                ListFormat::Table => print_fixtures(&listings),
+                ListFormat::Json => println!("{}", render_listings_json(&listings)),
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Graph(graph_args)) => {
+            let settings = Settings {
+                infer_types: true,
+                ..settings
+            };
+            let files = get_files_list(&graph_args.path, args.recursive)?;
+            let (parsed_files, index) = load_and_index_files(files.iter(), &settings);
+            let mut graph = FixtureGraph::new(&parsed_files, &index);
+            if let Some(name) = graph_args.filter {
+                graph = graph.closure(&name)?;
+            }
+
+            match graph_args.format {
+                GraphFormat::Dot => println!("{}", graph.to_dot()),
+                GraphFormat::Mermaid => println!("{}", graph.to_mermaid()),
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
+    }
 
     let files = get_files_list(&args.file, args.recursive)?;
 
     let pb = get_progress_bar(files.len() as u64);
-    for file in check_and_parse_file(files.iter().progress_with(pb)) {
-        print!("{}", file);
+    let (mut parsed_files, index) =
+        check_and_index_files(files.iter().progress_with(pb), &settings);
+    check_project(&mut parsed_files, &settings);
+
+    if let Some(path) = args.write_baseline {
+        let baseline = Baseline::new(&parsed_files);
+        baseline.save(&path)?;
+        eprintln!(
+            "Recorded {} diagnostics in {}.",
+            baseline.diagnostics.len(),
+            path.display()
+        );
+        return Ok(ExitCode::SUCCESS);
     }
+    if let Some(path) = args.baseline {
+        Baseline::load(&path)?.apply(&mut parsed_files);
+    }
+
+    if args.fix || args.diff {
+        let fixed: Vec<FixedFile> = parsed_files
+            .iter_mut()
+            .filter_map(|file| fix_file(file, &index, args.unsafe_fixes))
+            .collect();
+
+        if args.diff {
+            fixed.iter().for_each(|file| print!("{}", file.diff()));
+            return Ok(if fixed.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            });
+        }
+
+        for file in fixed.iter() {
+            file.write()?;
+        }
+        eprintln!(
+            "Fixed {} diagnostics.",
+            fixed.iter().map(|file| file.fixes).sum::<usize>()
+        );
+    }
+
+    let has_errors = parsed_files
+        .iter()
+        .flat_map(|file| file.errors.iter())
+        .any(|err| err.severity() == Severity::Error);
+
+    match args.output_format {
+        OutputFormat::Concise => parsed_files.iter().for_each(|file| print!("{}", file)),
+        OutputFormat::Rich => parsed_files
+            .iter()
+            .for_each(|file| print!("{}", render_rich(file, &index, true))),
+        OutputFormat::Json => println!("{}", render_json(&parsed_files)),
+    }
+
+    if has_errors {
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use clap::Parser;
+    use tempfile::TempDir;
+
+    use super::{resolve_subcommand_paths, Args, Command};
+
+    fn parse(args: &[&str], current_dir: &std::path::Path) -> Args {
+        let args = args.iter().map(Into::into).collect();
+        Args::try_parse_from(resolve_subcommand_paths(args, current_dir)).unwrap()
+    }
+
+    #[test]
+    fn assert_existing_path_beats_subcommand() {
+        let dir = TempDir::new().unwrap();
+
+        let args = parse(&["pytest_typechecker", "fixtures", "tests"], dir.path());
+        assert!(matches!(args.command, Some(Command::Fixtures(_))));
 
-    Ok(())
+        fs::create_dir(dir.path().join("fixtures")).unwrap();
+        let args = parse(&["pytest_typechecker", "fixtures", "-r"], dir.path());
+        assert!(args.command.is_none());
+        assert_eq!(args.file, vec![PathBuf::from("./fixtures")]);
+        assert!(args.recursive);
+
+        let args = parse(&["pytest_typechecker", "graph", "tests"], dir.path());
+        assert!(matches!(args.command, Some(Command::Graph(_))));
+    }
 }
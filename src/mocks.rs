@@ -0,0 +1,194 @@
+use rustpython_ast::{ArgWithDefault, Expr, ExprCall, StmtFunctionDef};
+
+use crate::nodes::get_keyword;
+
+/// Annotations accepted for arguments receiving a mock created by `mock.patch`.
+pub const MOCK_TYPES: &[&str] = &[
+    "AsyncMock",
+    "MagicMock",
+    "Mock",
+    "NonCallableMagicMock",
+    "NonCallableMock",
+    "PropertyMock",
+];
+
+/// How a `mock.patch` decorator passes the created mocks to the decorated function.
+#[derive(Debug, PartialEq)]
+enum Injection {
+    /// One leading positional argument. `typed` is false when the mock class is chosen through
+    /// `new_callable=`, in which case its type is unknown.
+    Positional {
+        typed: bool,
+    },
+    /// Keyword arguments, as done by `patch.multiple` for values set to `DEFAULT`.
+    Keywords(Vec<String>),
+    None,
+}
+
+fn is_default(expr: &Expr) -> bool {
+    match expr {
+        Expr::Name(name) => name.id.as_str() == "DEFAULT",
+        Expr::Attribute(attribute) => attribute.attr.as_str() == "DEFAULT",
+        _ => false,
+    }
+}
+
+/// Returns the `patch` variant called by `call`: `Some("")` for `patch(...)` itself, and e.g.
+/// `Some("object")` for `patch.object(...)`.
+fn get_patch_kind(call: &ExprCall) -> Option<&str> {
+    match &*call.func {
+        Expr::Name(name) if name.id.as_str() == "patch" => Some(""),
+        Expr::Attribute(attribute) if attribute.attr.as_str() == "patch" => Some(""),
+        Expr::Attribute(attribute) => match &*attribute.value {
+            Expr::Name(name) if name.id.as_str() == "patch" => Some(attribute.attr.as_str()),
+            Expr::Attribute(value) if value.attr.as_str() == "patch" => {
+                Some(attribute.attr.as_str())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn get_injection(decorator: &Expr) -> Injection {
+    let Expr::Call(call) = decorator else {
+        return Injection::None;
+    };
+    let typed = get_keyword(call, "new_callable").is_none();
+
+    match get_patch_kind(call) {
+        // `patch(target, new=DEFAULT, ...)`
+        Some("") if call.args.len() < 2 && get_keyword(call, "new").is_none() => {
+            Injection::Positional { typed }
+        }
+        // `patch.object(target, attribute, new=DEFAULT, ...)`
+        Some("object") if call.args.len() < 3 && get_keyword(call, "new").is_none() => {
+            Injection::Positional { typed }
+        }
+        // `patch.multiple(target, name=DEFAULT, ...)`
+        Some("multiple") => Injection::Keywords(
+            call.keywords
+                .iter()
+                .filter(|k| is_default(&k.value))
+                .filter_map(|k| k.arg.as_ref().map(|a| a.to_string()))
+                .collect(),
+        ),
+        _ => Injection::None,
+    }
+}
+
+/// Returns the number of leading positional arguments injected by `unittest.mock.patch`
+/// decorators, mirroring pytest's `num_mock_patch_args`. A patch providing its own replacement,
+/// positionally or through `new=`, does not inject an argument, and neither does `patch.dict`.
+pub fn num_mock_patch_args(decorators: &[Expr]) -> usize {
+    decorators
+        .iter()
+        .filter(|decorator| matches!(get_injection(decorator), Injection::Positional { .. }))
+        .count()
+}
+
+/// Returns the names of the keyword arguments injected by `patch.multiple` decorators.
+pub fn get_mock_keyword_args(decorators: &[Expr]) -> Vec<String> {
+    decorators
+        .iter()
+        .flat_map(|decorator| match get_injection(decorator) {
+            Injection::Keywords(names) => names,
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Returns the arguments of `func` that receive a mock of a known type. Decorators apply bottom-up,
+/// so the lowest `patch` decorator provides the first argument.
+pub fn get_mock_arguments(func: &StmtFunctionDef) -> Vec<&ArgWithDefault> {
+    let positional: Vec<bool> = func
+        .decorator_list
+        .iter()
+        .rev()
+        .filter_map(|decorator| match get_injection(decorator) {
+            Injection::Positional { typed } => Some(typed),
+            _ => None,
+        })
+        .collect();
+    let keywords = get_mock_keyword_args(&func.decorator_list);
+
+    let arguments: Vec<&ArgWithDefault> = func
+        .args
+        .args
+        .iter()
+        .chain(func.args.kwonlyargs.iter())
+        .filter(|arg| arg.default.is_none())
+        .collect();
+
+    arguments
+        .iter()
+        .zip(positional)
+        .filter(|(_, typed)| *typed)
+        .map(|(arg, _)| *arg)
+        .chain(
+            arguments
+                .iter()
+                .filter(|arg| keywords.iter().any(|k| k == arg.def.arg.as_str()))
+                .copied(),
+        )
+        .collect()
+}
+
+/// Checks whether an annotation is one of the `unittest.mock` classes, whether it is written as
+/// `MagicMock`, `mock.MagicMock` or `unittest.mock.MagicMock`.
+pub fn is_mock_annotation(expr: &Expr) -> bool {
+    let name = match expr {
+        Expr::Name(name) => name.id.as_str(),
+        Expr::Attribute(attribute) => attribute.attr.as_str(),
+        _ => return false,
+    };
+    MOCK_TYPES.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_ast::{Stmt, StmtFunctionDef, Suite};
+    use rustpython_parser::Parse;
+
+    use super::{get_mock_arguments, get_mock_keyword_args, num_mock_patch_args};
+
+    fn parse_function(source: &str) -> StmtFunctionDef {
+        let stmts = Suite::parse(source, "<test>").unwrap();
+        let Some(Stmt::FunctionDef(func)) = stmts.into_iter().next() else {
+            panic!("Expected a function.")
+        };
+        func
+    }
+
+    #[test]
+    fn assert_num_mock_patch_args() {
+        let func = parse_function(
+            "@mock.patch('a')\n@patch('b', new=1)\n@patch('c', 2)\n@patch('d')\ndef test(): pass\n",
+        );
+
+        assert_eq!(num_mock_patch_args(&func.decorator_list), 2);
+    }
+
+    #[test]
+    fn assert_patch_variants() {
+        let func = parse_function(
+            "@patch.object(a, 'b')\n@patch.object(a, 'c', 1)\n@mock.patch.dict('os.environ', {})\n@mock.patch.multiple('os', sep=DEFAULT, name='x')\ndef test(): pass\n",
+        );
+
+        assert_eq!(num_mock_patch_args(&func.decorator_list), 1);
+        assert_eq!(get_mock_keyword_args(&func.decorator_list), vec!["sep"]);
+    }
+
+    #[test]
+    fn assert_get_mock_arguments() {
+        let func = parse_function(
+            "@patch('a')\n@patch('b', new_callable=PropertyMock)\n@patch.multiple('os', sep=DEFAULT)\ndef test(b, a, user, sep): pass\n",
+        );
+        let names: Vec<String> = get_mock_arguments(&func)
+            .iter()
+            .map(|a| a.def.arg.to_string())
+            .collect();
+
+        assert_eq!(names, vec!["a", "sep"]);
+    }
+}
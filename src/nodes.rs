@@ -1,4 +1,6 @@
-use rustpython_ast::{Expr, ExprAttribute, ExprCall, ExprName, StmtFunctionDef};
+use rustpython_ast::{
+    Constant, Expr, ExprAttribute, ExprCall, ExprConstant, ExprName, Stmt, StmtFunctionDef,
+};
 
 pub trait FunctionUtil {
     fn is_pytest_fixture(&self) -> bool;
@@ -31,10 +33,103 @@ impl FunctionUtil for StmtFunctionDef {
     }
 }
 
+pub trait FixtureUtil {
+    fn fixture_keyword(&self, keyword: &str) -> Option<&Expr>;
+    fn is_autouse_fixture(&self) -> bool;
+}
+
+impl FixtureUtil for StmtFunctionDef {
+    fn fixture_keyword(&self, keyword: &str) -> Option<&Expr> {
+        self.decorator_list.iter().find_map(|x| match x {
+            Expr::Call(v) if v.is_pytest_fixture() => get_keyword(v, keyword),
+            _ => None,
+        })
+    }
+
+    fn is_autouse_fixture(&self) -> bool {
+        matches!(
+            self.fixture_keyword("autouse"),
+            Some(Expr::Constant(ExprConstant {
+                value: Constant::Bool(true),
+                ..
+            }))
+        )
+    }
+}
+
+pub fn get_keyword<'a>(call: &'a ExprCall, keyword: &str) -> Option<&'a Expr> {
+    call.keywords
+        .iter()
+        .find(|k| k.arg.as_ref().is_some_and(|arg| arg == keyword))
+        .map(|k| &k.value)
+}
+
+pub fn get_string_literal(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Constant(ExprConstant {
+            value: Constant::Str(value),
+            ..
+        }) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Checks whether `expr` is `pytest.mark.<mark>`.
+pub fn is_pytest_mark(expr: &Expr, mark: &str) -> bool {
+    let Expr::Attribute(ExprAttribute { value, attr, .. }) = expr else {
+        return false;
+    };
+    let Expr::Attribute(ExprAttribute {
+        value: module,
+        attr: mark_attr,
+        ..
+    }) = &**value
+    else {
+        return false;
+    };
+    let is_pytest_name = matches!(&**module, Expr::Name(ExprName { id, .. }) if id == "pytest");
+
+    is_pytest_name && mark_attr == "mark" && attr == mark
+}
+
+/// Returns the fixture names requested by a `pytest.mark.usefixtures(...)` expression, or by a
+/// list/tuple of marks as used in `pytestmark` assignments.
+pub fn get_usefixtures(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::Call(call) if is_pytest_mark(&call.func, "usefixtures") => {
+            call.args.iter().filter_map(get_string_literal).collect()
+        }
+        Expr::List(list) => list.elts.iter().flat_map(get_usefixtures).collect(),
+        Expr::Tuple(tuple) => tuple.elts.iter().flat_map(get_usefixtures).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns the value assigned to the module-level `pytestmark` variable, if any.
+pub fn get_pytestmark(stmts: &[Stmt]) -> Option<&Expr> {
+    stmts.iter().rev().find_map(|s| match s {
+        Stmt::Assign(assign)
+            if assign.targets.iter().any(
+                |t| matches!(t, Expr::Name(ExprName { id, .. }) if id == "pytestmark"),
+            ) =>
+        {
+            Some(&*assign.value)
+        }
+        Stmt::AnnAssign(assign)
+            if matches!(&*assign.target, Expr::Name(ExprName { id, .. }) if id == "pytestmark") =>
+        {
+            assign.value.as_deref()
+        }
+        _ => None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use super::FunctionUtil;
+    use super::{get_usefixtures, FixtureUtil, FunctionUtil};
     use crate::test_utils::{get_fixture, get_function};
+    use rustpython_ast::Expr;
+    use rustpython_parser::Parse;
 
     #[test]
     fn assert_pytest_fixture() {
@@ -49,4 +144,22 @@ mod tests {
 
         assert!(!func.as_function_def_stmt().unwrap().is_pytest_fixture());
     }
+
+    #[test]
+    fn assert_not_autouse_fixture() {
+        let func = get_fixture("fixture", Vec::new(), None);
+
+        assert!(!func.as_function_def_stmt().unwrap().is_autouse_fixture());
+    }
+
+    #[test]
+    fn assert_get_usefixtures() {
+        let expr = Expr::parse(
+            "[pytest.mark.usefixtures('a', 'b'), pytest.mark.slow, pytest.mark.usefixtures('c')]",
+            "<test>",
+        )
+        .unwrap();
+
+        assert_eq!(get_usefixtures(&expr), vec!["a", "b", "c"]);
+    }
 }
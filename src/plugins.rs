@@ -0,0 +1,326 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use rustpython_ast::{Expr, Stmt};
+
+use crate::{
+    files::{load_file, parsed_python_file::ParsedPythonFile},
+    nodes::get_string_literal,
+};
+
+/// A `from module import name as alias` statement, which may bring fixtures into a module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureImport {
+    pub module: String,
+    /// Number of leading dots of a relative import.
+    pub level: usize,
+    /// Imported names and the names they are bound to. `None` for `from module import *`.
+    pub names: Option<Vec<(String, String)>>,
+}
+
+/// Returns the modules listed in the module-level `pytest_plugins` variable.
+pub fn get_pytest_plugins(stmts: &[Stmt]) -> Vec<String> {
+    let value = stmts.iter().rev().find_map(|s| match s {
+        Stmt::Assign(assign)
+            if assign.targets.iter().any(
+                |t| matches!(t.as_name_expr(), Some(n) if n.id.as_str() == "pytest_plugins"),
+            ) =>
+        {
+            Some(&*assign.value)
+        }
+        _ => None,
+    });
+
+    match value {
+        Some(Expr::List(list)) => list.elts.iter().filter_map(get_string_literal).collect(),
+        Some(Expr::Tuple(tuple)) => tuple.elts.iter().filter_map(get_string_literal).collect(),
+        Some(expr) => get_string_literal(expr).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Returns the module-level `from ... import ...` statements.
+pub fn get_fixture_imports(stmts: &[Stmt]) -> Vec<FixtureImport> {
+    stmts
+        .iter()
+        .filter_map(|s| match s {
+            Stmt::ImportFrom(import) => {
+                let names = if import.names.iter().any(|a| a.name.as_str() == "*") {
+                    None
+                } else {
+                    Some(
+                        import
+                            .names
+                            .iter()
+                            .map(|a| {
+                                let alias = a.asname.as_ref().unwrap_or(&a.name);
+                                (a.name.to_string(), alias.to_string())
+                            })
+                            .collect(),
+                    )
+                };
+
+                Some(FixtureImport {
+                    module: import
+                        .module
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_default(),
+                    level: import.level.map(|l| l.to_usize()).unwrap_or_default(),
+                    names,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn module_file(base: &Path, module: &str) -> Option<PathBuf> {
+    // `from . import name` imports from the package itself.
+    if module.is_empty() {
+        let init = base.join("__init__.py");
+        return init.is_file().then_some(init);
+    }
+
+    let path = module
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .fold(base.to_path_buf(), |path, part| path.join(part));
+
+    [path.with_extension("py"), path.join("__init__.py")]
+        .into_iter()
+        .find(|candidate| candidate.is_file())
+}
+
+/// Resolves a module imported from `importing_file` to a file. Relative imports are resolved from
+/// the importing file's package, absolute ones from the source roots.
+pub fn resolve_module(
+    module: &str,
+    level: usize,
+    importing_file: &Path,
+    source_roots: &[PathBuf],
+) -> Option<PathBuf> {
+    if level > 0 {
+        let base = importing_file.ancestors().nth(level)?;
+        return module_file(base, module);
+    }
+
+    if source_roots.is_empty() {
+        return module_file(Path::new("."), module);
+    }
+
+    source_roots
+        .iter()
+        .find_map(|root| module_file(root, module))
+}
+
+/// Returns the modules `file` may get fixtures from, with the names to look for in each of them.
+/// `wanted` holds the names `file` itself is expected to provide, `None` meaning all of its
+/// fixtures, as for the checked files, `pytest_plugins` modules and `from module import *`. Only
+/// the imports binding wanted names are followed, and `pytest_plugins` only when all fixtures are.
+pub fn get_fixture_sources(
+    file: &ParsedPythonFile,
+    wanted: Option<&[String]>,
+    source_roots: &[PathBuf],
+) -> Vec<(PathBuf, Option<Vec<String>>)> {
+    let path = Path::new(&file.file.filename);
+    let is_wanted = |alias: &str| {
+        !file.fixtures.contains_key(alias) && wanted.is_none_or(|w| w.iter().any(|n| n == alias))
+    };
+    let mut sources = Vec::new();
+
+    if wanted.is_none() {
+        sources.extend(
+            get_pytest_plugins(&file.body)
+                .iter()
+                .filter_map(|plugin| Some((resolve_module(plugin, 0, path, source_roots)?, None))),
+        );
+    }
+
+    for import in get_fixture_imports(&file.body) {
+        let names = match (&import.names, wanted) {
+            (None, None) => None,
+            (None, Some(wanted)) => Some(wanted.iter().filter(|n| is_wanted(n)).cloned().collect()),
+            (Some(names), _) => Some(
+                names
+                    .iter()
+                    .filter(|(_, alias)| is_wanted(alias))
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            ),
+        };
+        if names
+            .as_ref()
+            .is_some_and(|names: &Vec<String>| names.is_empty())
+        {
+            continue;
+        }
+        if let Some(module) = resolve_module(&import.module, import.level, path, source_roots) {
+            sources.push((module, names));
+        }
+    }
+
+    sources
+}
+
+/// Reads and parses the modules `files` may get fixtures from, and recursively the ones those
+/// modules re-export fixtures from, skipping the files that are already loaded. A module imported
+/// for names it neither defines as fixtures nor imports is parsed, but its imports are not
+/// followed.
+pub fn load_referenced_modules(
+    files: &[&ParsedPythonFile],
+    source_roots: &[PathBuf],
+) -> Vec<ParsedPythonFile> {
+    let normalize = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+    let provided: HashSet<PathBuf> = files
+        .iter()
+        .map(|f| normalize(Path::new(&f.file.filename)))
+        .collect();
+    let mut pending: Vec<(PathBuf, Option<Vec<String>>)> = files
+        .iter()
+        .flat_map(|f| get_fixture_sources(f, None, source_roots))
+        .collect();
+    let mut visited: HashSet<(PathBuf, Option<String>)> = HashSet::new();
+    let mut positions: HashMap<PathBuf, usize> = HashMap::new();
+    let mut modules: Vec<ParsedPythonFile> = Vec::new();
+
+    while let Some((path, names)) = pending.pop() {
+        let normalized = normalize(&path);
+        if provided.contains(&normalized) {
+            continue;
+        }
+
+        let names = match names {
+            None if visited.insert((normalized.clone(), None)) => None,
+            None => continue,
+            Some(names) => {
+                let names: Vec<String> = names
+                    .into_iter()
+                    .filter(|name| visited.insert((normalized.clone(), Some(name.clone()))))
+                    .collect();
+                if names.is_empty() {
+                    continue;
+                }
+                Some(names)
+            }
+        };
+
+        let position = *positions.entry(normalized).or_insert_with(|| {
+            modules.push(load_file(&path));
+            modules.len() - 1
+        });
+        pending.extend(get_fixture_sources(
+            &modules[position],
+            names.as_deref(),
+            source_roots,
+        ));
+    }
+
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use rustpython_ast::Suite;
+    use rustpython_parser::Parse;
+
+    use crate::files::load_file;
+
+    use super::{
+        get_fixture_imports, get_pytest_plugins, load_referenced_modules, resolve_module,
+        FixtureImport,
+    };
+
+    #[test]
+    fn assert_get_pytest_plugins() {
+        let stmts = Suite::parse("pytest_plugins = ['a.b', 'c']\n", "<test>").unwrap();
+        assert_eq!(get_pytest_plugins(&stmts), vec!["a.b", "c"]);
+
+        let stmts = Suite::parse("pytest_plugins = 'a.b'\n", "<test>").unwrap();
+        assert_eq!(get_pytest_plugins(&stmts), vec!["a.b"]);
+    }
+
+    #[test]
+    fn assert_get_fixture_imports() {
+        let stmts = Suite::parse("from ..a import b as c, d\nfrom e import *\n", "<test>").unwrap();
+
+        assert_eq!(
+            get_fixture_imports(&stmts),
+            vec![
+                FixtureImport {
+                    module: "a".to_owned(),
+                    level: 2,
+                    names: Some(vec![
+                        ("b".to_owned(), "c".to_owned()),
+                        ("d".to_owned(), "d".to_owned())
+                    ]),
+                },
+                FixtureImport {
+                    module: "e".to_owned(),
+                    level: 0,
+                    names: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn assert_resolve_module() {
+        let roots = [PathBuf::from("./python-examples")];
+        let importing_file = Path::new("./python-examples/plugins/test_plugins.py");
+
+        assert_eq!(
+            resolve_module("plugins.shared.db", 0, importing_file, &roots),
+            Some(PathBuf::from("./python-examples/plugins/shared/db.py"))
+        );
+        assert_eq!(
+            resolve_module("plugins.shared", 0, importing_file, &roots),
+            Some(PathBuf::from(
+                "./python-examples/plugins/shared/__init__.py"
+            ))
+        );
+        assert_eq!(
+            resolve_module("shared.users", 1, importing_file, &roots),
+            Some(PathBuf::from("./python-examples/plugins/shared/users.py"))
+        );
+        assert_eq!(
+            resolve_module(
+                "",
+                1,
+                Path::new("./python-examples/plugins/shared/db.py"),
+                &roots
+            ),
+            Some(PathBuf::from(
+                "./python-examples/plugins/shared/__init__.py"
+            ))
+        );
+        assert_eq!(resolve_module("", 1, importing_file, &roots), None);
+        assert_eq!(resolve_module("missing", 0, importing_file, &roots), None);
+    }
+
+    #[test]
+    fn assert_load_referenced_modules() {
+        let roots = [PathBuf::from("./python-examples")];
+        let files = [
+            load_file(Path::new("./python-examples/plugins/test_plugins.py")),
+            load_file(Path::new("./python-examples/plugins/conftest.py")),
+        ];
+        let modules = load_referenced_modules(&files.iter().collect::<Vec<_>>(), &roots);
+
+        let mut filenames: Vec<&str> = modules.iter().map(|m| m.file.filename.as_str()).collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            vec![
+                "./python-examples/plugins/shared/db.py",
+                "./python-examples/plugins/shared/models.py",
+                "./python-examples/plugins/shared/users.py",
+            ]
+        );
+    }
+}
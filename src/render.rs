@@ -0,0 +1,492 @@
+use std::{collections::HashMap, fmt::Write};
+
+use colored::{Color, ColoredString, Colorize};
+use serde::Serialize;
+
+use crate::{
+    analysis_error::{AnalysisError, Severity},
+    files::parsed_python_file::ParsedPythonFile,
+    fixture_index::{FixtureDefinition, FixtureIndex},
+    location::{function_span_at, primary_span, SourceLine},
+};
+
+/// Returns `text` with its color and style, or as plain text when `color` is unset, regardless of
+/// the global settings of `colored`.
+pub fn paint(text: ColoredString, color: bool) -> String {
+    if color {
+        text.to_string()
+    } else {
+        text.input.clone()
+    }
+}
+
+/// A labelled span of a code frame.
+#[derive(Debug)]
+struct Label {
+    span: SourceLine,
+    message: String,
+}
+
+/// The source context of a diagnostic: the primary span it points at, the secondary spans it
+/// refers to, and the notes and suggestions shown below them.
+#[derive(Debug, Default)]
+struct Frame {
+    primary: Option<Label>,
+    secondary: Vec<Label>,
+    notes: Vec<String>,
+    helps: Vec<String>,
+}
+
+fn label(span: Option<SourceLine>, message: impl Into<String>) -> Option<Label> {
+    Some(Label {
+        span: span?,
+        message: message.into(),
+    })
+}
+
+/// Returns the definition of the fixture an argument of `function_name` receives, which is the
+/// overridden fixture when a fixture requests its own name.
+pub fn requested_fixture(
+    file: &ParsedPythonFile,
+    index: &FixtureIndex,
+    fixtures: &HashMap<String, FixtureDefinition>,
+    function_name: &str,
+    fixture_name: &str,
+) -> Option<FixtureDefinition> {
+    if function_name == fixture_name && file.fixtures.contains_key(fixture_name) {
+        return index.overridden_fixture(file, fixture_name).cloned();
+    }
+    fixtures.get(fixture_name).cloned()
+}
+
+fn fixture_label(fixture: &FixtureDefinition, expected_type: &str) -> Option<Label> {
+    label(
+        fixture.returns_source.clone(),
+        format!(
+            "fixture {} returns {}",
+            fixture.function.name, expected_type
+        ),
+    )
+}
+
+fn primary_message(err: &AnalysisError) -> String {
+    match err {
+        AnalysisError::IncorrectArgumentType { provided_type, .. }
+        | AnalysisError::IncorrectInferredArgumentType { provided_type, .. }
+        | AnalysisError::IncorrectFixtureValueType { provided_type, .. }
+        | AnalysisError::IncorrectStrategyType { provided_type, .. } => {
+            format!("specified type is {}", provided_type)
+        }
+        AnalysisError::MissingArgumentType { .. } => "missing type annotation".to_owned(),
+        AnalysisError::FixtureDoesNotExist { argument_name, .. } => {
+            format!("no fixture named {} is visible here", argument_name)
+        }
+        AnalysisError::FixtureMissingReturnType { .. } => {
+            "missing return type annotation".to_owned()
+        }
+        AnalysisError::FixtureCalledDirectly { .. } => "fixture called here".to_owned(),
+        AnalysisError::IncorrectMockArgumentType { .. } => "receives a mock".to_owned(),
+        AnalysisError::DuplicateFixture { .. } => "redefined here".to_owned(),
+        AnalysisError::IncompatibleFixtureOverride { fixture_type, .. } => {
+            format!("returns {}", fixture_type)
+        }
+        AnalysisError::UnusedFixture { .. } => "never requested".to_owned(),
+        AnalysisError::UnsupportedAnnotation { .. } => "not a supported type".to_owned(),
+        AnalysisError::UnparsableFile { message, .. } => message.clone(),
+        AnalysisError::UnreadableFile { reason } => reason.clone(),
+        AnalysisError::UnusedSuppression { .. } => "suppresses nothing".to_owned(),
+    }
+}
+
+fn build_frame(err: &AnalysisError, file: &ParsedPythonFile, index: &FixtureIndex) -> Frame {
+    let fixtures = index.visible_fixtures(file);
+    let mut frame = Frame {
+        primary: label(primary_span(err, file), primary_message(err)),
+        ..Frame::default()
+    };
+
+    match err {
+        AnalysisError::IncorrectArgumentType {
+            function_name,
+            argument_name,
+            expected_type,
+            ..
+        } => {
+            let fixture = requested_fixture(file, index, &fixtures, function_name, argument_name);
+            if let Some(fixture) = &fixture {
+                frame
+                    .secondary
+                    .extend(fixture_label(fixture, expected_type));
+                frame.notes.push(format!(
+                    "fixture {} is defined at {}",
+                    argument_name,
+                    fixture.location()
+                ));
+            }
+            frame.helps.push(format!(
+                "annotate argument {} as {}",
+                argument_name, expected_type
+            ));
+        }
+        AnalysisError::IncorrectInferredArgumentType {
+            function_name,
+            argument_name,
+            inferred_type,
+            ..
+        } => {
+            let fixture = requested_fixture(file, index, &fixtures, function_name, argument_name);
+            if let Some(fixture) = &fixture {
+                frame.notes.push(format!(
+                    "fixture {} defined at {} has no return annotation, {} is inferred from its body",
+                    argument_name,
+                    fixture.location(),
+                    inferred_type
+                ));
+            }
+            frame.helps.push(format!(
+                "annotate fixture {} with its return type, or argument {} as {}",
+                argument_name, argument_name, inferred_type
+            ));
+        }
+        AnalysisError::MissingArgumentType {
+            function_name,
+            argument_name,
+            ..
+        } => {
+            let fixture = requested_fixture(file, index, &fixtures, function_name, argument_name);
+            if let Some(expected_type) = fixture
+                .as_ref()
+                .and_then(|f| f.return_annotation())
+                .map(|a| a.written)
+            {
+                frame.secondary.extend(
+                    fixture
+                        .as_ref()
+                        .and_then(|f| fixture_label(f, &expected_type)),
+                );
+                frame.helps.push(format!(
+                    "annotate argument {} as {}",
+                    argument_name, expected_type
+                ));
+            }
+        }
+        AnalysisError::FixtureDoesNotExist { suggestions, .. } => {
+            frame.notes.push(
+                "fixtures are looked up in the file, its conftest.py files, imported modules and pytest_plugins".to_owned(),
+            );
+            frame.helps.extend(
+                suggestions
+                    .iter()
+                    .map(|suggestion| format!("did you mean {}?", suggestion)),
+            );
+        }
+        AnalysisError::FixtureMissingReturnType {
+            fixture_name,
+            suggested_type,
+        } => {
+            frame.helps.push(match suggested_type {
+                Some(suggested_type) => format!(
+                    "its body suggests `-> {}`, which `--fix` can insert",
+                    suggested_type
+                ),
+                None => format!(
+                    "annotate the return type of fixture {}, e.g. `def {}() -> int:`",
+                    fixture_name, fixture_name
+                ),
+            });
+        }
+        AnalysisError::IncorrectFixtureValueType {
+            fixture_name,
+            expected_type,
+            ..
+        } => {
+            if let Some(fixture) = fixtures.get(fixture_name) {
+                frame
+                    .secondary
+                    .extend(fixture_label(fixture, expected_type));
+            }
+        }
+        AnalysisError::FixtureCalledDirectly {
+            function_name,
+            fixture_name,
+            ..
+        } => {
+            frame.helps.push(format!(
+                "add {} to the arguments of {} and pytest will provide its value",
+                fixture_name, function_name
+            ));
+        }
+        AnalysisError::IncorrectMockArgumentType { argument_name, .. } => {
+            frame.helps.push(format!(
+                "annotate argument {} as unittest.mock.MagicMock",
+                argument_name
+            ));
+        }
+        AnalysisError::DuplicateFixture {
+            fixture_name,
+            first_line,
+            ..
+        } => {
+            frame.secondary.extend(label(
+                function_span_at(file, fixture_name, *first_line),
+                "first defined here",
+            ));
+            frame
+                .notes
+                .push("only the last definition is used by pytest".to_owned());
+        }
+        AnalysisError::IncompatibleFixtureOverride {
+            fixture_name,
+            overridden_type,
+            ..
+        } => {
+            if let Some(overridden) = index.overridden_fixture(file, fixture_name) {
+                frame
+                    .secondary
+                    .extend(fixture_label(overridden, overridden_type));
+            }
+        }
+        AnalysisError::UnusedFixture { .. } => {
+            frame
+                .helps
+                .push("remove the fixture or pass it with --allow-unused".to_owned());
+        }
+        AnalysisError::IncorrectStrategyType { .. }
+        | AnalysisError::UnsupportedAnnotation { .. }
+        | AnalysisError::UnparsableFile { .. }
+        | AnalysisError::UnreadableFile { .. } => {}
+        AnalysisError::UnusedSuppression { .. } => {
+            frame
+                .helps
+                .push("remove the suppression comment".to_owned());
+        }
+    }
+
+    frame
+}
+
+fn write_label(
+    out: &mut String,
+    label: &Label,
+    gutter: usize,
+    marker: char,
+    label_color: Color,
+    color: bool,
+) -> std::fmt::Result {
+    let span = &label.span;
+    let underline = marker
+        .to_string()
+        .repeat(span.end_column.saturating_sub(span.start_column).max(1));
+
+    writeln!(out, "{} {}", " ".repeat(gutter), paint("|".blue(), color))?;
+    writeln!(
+        out,
+        "{} {} {}",
+        paint(format!("{:>gutter$}", span.line).blue(), color),
+        paint("|".blue(), color),
+        span.text
+    )?;
+    writeln!(
+        out,
+        "{} {} {}{}",
+        " ".repeat(gutter),
+        paint("|".blue(), color),
+        " ".repeat(span.start_column.saturating_sub(1)),
+        paint(
+            format!("{} {}", underline, label.message)
+                .color(label_color)
+                .bold(),
+            color
+        )
+    )
+}
+
+/// Renders the diagnostics of a file with rustc-style code frames: the offending source line with
+/// the span underlined, the related definitions, and notes and suggestions. Colors are only used
+/// when `color` is set.
+pub fn render_rich(file: &ParsedPythonFile, index: &FixtureIndex, color: bool) -> String {
+    let mut out = String::new();
+
+    for err in &file.errors {
+        let frame = build_frame(err, file, index);
+        let label_color = match err.severity() {
+            Severity::Error => Color::Red,
+            Severity::Warning => Color::Yellow,
+        };
+        let gutter = frame
+            .primary
+            .iter()
+            .chain(frame.secondary.iter())
+            .map(|l| l.span.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        // Writing to a `String` cannot fail.
+        let _ = writeln!(
+            out,
+            "{} {}",
+            paint(err.rule().code().bold(), color),
+            err.message(color)
+        );
+        let location = match &frame.primary {
+            Some(primary) => format!(
+                "{}:{}:{}",
+                primary.span.filename, primary.span.line, primary.span.start_column
+            ),
+            None => file.file.filename.clone(),
+        };
+        let _ = writeln!(
+            out,
+            "{}{} {}",
+            " ".repeat(gutter),
+            paint("-->".blue(), color),
+            location
+        );
+
+        if let Some(primary) = &frame.primary {
+            let _ = write_label(&mut out, primary, gutter, '^', label_color, color);
+        }
+        for secondary in &frame.secondary {
+            if secondary.span.filename != file.file.filename {
+                let _ = writeln!(
+                    out,
+                    "{}{} {}:{}:{}",
+                    " ".repeat(gutter),
+                    paint(":::".blue(), color),
+                    secondary.span.filename,
+                    secondary.span.line,
+                    secondary.span.start_column
+                );
+            }
+            let _ = write_label(&mut out, secondary, gutter, '-', Color::Blue, color);
+        }
+
+        if !frame.notes.is_empty() || !frame.helps.is_empty() {
+            let _ = writeln!(out, "{} {}", " ".repeat(gutter), paint("|".blue(), color));
+        }
+        for note in &frame.notes {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                " ".repeat(gutter),
+                paint("= note:".bold(), color),
+                note
+            );
+        }
+        for help in &frame.helps {
+            let _ = writeln!(
+                out,
+                "{} {} {}",
+                " ".repeat(gutter),
+                paint("= help:".bold(), color),
+                help
+            );
+        }
+        let _ = writeln!(out);
+    }
+
+    out
+}
+
+/// A diagnostic as written by [`render_json`].
+#[derive(Debug, Serialize)]
+struct JsonDiagnostic<'a> {
+    filename: &'a str,
+    code: &'static str,
+    severity: Severity,
+    message: String,
+    #[serde(flatten)]
+    error: &'a AnalysisError,
+}
+
+/// Renders the diagnostics of all files as a JSON array. Besides the filename, rule code, severity
+/// and message, each diagnostic holds its `type` and the fields of that type, e.g. the suggestions of
+/// `FixtureDoesNotExist`. Messages are written without colors.
+pub fn render_json(files: &[ParsedPythonFile]) -> String {
+    let diagnostics: Vec<JsonDiagnostic> = files
+        .iter()
+        .flat_map(|file| {
+            file.errors.iter().map(|err| JsonDiagnostic {
+                filename: &file.file.filename,
+                code: err.rule().code(),
+                severity: err.severity(),
+                message: err.message(false),
+                error: err,
+            })
+        })
+        .collect();
+
+    // Diagnostics only hold strings, numbers and lists, which always serialize.
+    serde_json::to_string_pretty(&diagnostics).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{check_and_index_files, settings::Settings};
+
+    use super::{render_json, render_rich};
+
+    #[test]
+    fn assert_render_rich() {
+        let files = [PathBuf::from(
+            "./python-examples/qualified/test_qualified.py",
+        )];
+        let (parsed, index) = check_and_index_files(files.iter(), &Settings::default());
+        let rendered = render_rich(&parsed[0], &index, false);
+
+        assert!(rendered.contains(" --> ./python-examples/qualified/test_qualified.py:21:25\n"));
+        assert!(rendered.contains("21 | def test_config(config: Config):\n"));
+        assert!(rendered.contains(
+            "   |                         ^^^^^^ specified type is test_qualified.Config\n"
+        ));
+        assert!(rendered.contains(" ::: ./python-examples/qualified/conftest.py:8:17\n"));
+        assert!(rendered.contains(
+            "   |                 ------ fixture config returns configparser.ConfigParser\n"
+        ));
+        assert!(
+            rendered.contains("   = help: annotate argument config as configparser.ConfigParser\n")
+        );
+    }
+
+    #[test]
+    fn assert_render_functions_sharing_their_name() {
+        let files = [PathBuf::from("./python-examples/fixes/test_same_name.py")];
+        let (parsed, index) = check_and_index_files(files.iter(), &Settings::default());
+        let rendered = render_rich(&parsed[0], &index, false);
+
+        assert!(rendered.contains(" --> ./python-examples/fixes/test_same_name.py:5:31\n"));
+        assert!(rendered.contains(" --> ./python-examples/fixes/test_same_name.py:10:25\n"));
+        assert!(rendered.contains("   |                         ^^^^ missing type annotation\n"));
+    }
+
+    #[test]
+    fn assert_render_json() {
+        let files = [PathBuf::from("./python-examples/test_getfixturevalue.py")];
+        let (parsed, _) = check_and_index_files(files.iter(), &Settings::default());
+        let rendered: serde_json::Value = serde_json::from_str(&render_json(&parsed)).unwrap();
+
+        let missing = rendered
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["type"] == "FixtureDoesNotExist")
+            .unwrap();
+        assert_eq!(
+            missing,
+            &serde_json::json!({
+                "filename": "./python-examples/test_getfixturevalue.py",
+                "code": "PTC004",
+                "severity": "error",
+                "message": "[FIXTURE_DOES_NOT_EXIST] Fixture user_nme used in function test_lookup does not exist. Did you mean user_name (./python-examples/test_getfixturevalue.py:6)?",
+                "type": "FixtureDoesNotExist",
+                "function_name": "test_lookup",
+                "argument_name": "user_nme",
+                "suggestions": [
+                    {"name": "user_name", "location": "./python-examples/test_getfixturevalue.py:6"}
+                ],
+            })
+        );
+    }
+}
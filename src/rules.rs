@@ -0,0 +1,265 @@
+use std::path::Path;
+
+use glob::Pattern;
+
+use crate::analysis_error::AnalysisError;
+
+/// The kinds of diagnostics, each with a stable code used to select or ignore it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    FixtureMissingReturnType,
+    IncorrectArgumentType,
+    MissingArgumentType,
+    IncorrectInferredArgumentType,
+    FixtureDoesNotExist,
+    IncorrectFixtureValueType,
+    FixtureCalledDirectly,
+    IncorrectStrategyType,
+    IncorrectMockArgumentType,
+    DuplicateFixture,
+    IncompatibleFixtureOverride,
+    UnusedFixture,
+    UnsupportedAnnotation,
+    UnparsableFile,
+    UnreadableFile,
+    UnusedSuppression,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 16] = [
+        Rule::FixtureMissingReturnType,
+        Rule::IncorrectArgumentType,
+        Rule::MissingArgumentType,
+        Rule::FixtureDoesNotExist,
+        Rule::IncorrectFixtureValueType,
+        Rule::FixtureCalledDirectly,
+        Rule::IncorrectStrategyType,
+        Rule::IncorrectMockArgumentType,
+        Rule::DuplicateFixture,
+        Rule::IncompatibleFixtureOverride,
+        Rule::UnusedFixture,
+        Rule::UnsupportedAnnotation,
+        Rule::UnparsableFile,
+        Rule::UnreadableFile,
+        Rule::UnusedSuppression,
+        Rule::IncorrectInferredArgumentType,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Rule::FixtureMissingReturnType => "PTC001",
+            Rule::IncorrectArgumentType => "PTC002",
+            Rule::MissingArgumentType => "PTC003",
+            Rule::FixtureDoesNotExist => "PTC004",
+            Rule::IncorrectFixtureValueType => "PTC005",
+            Rule::FixtureCalledDirectly => "PTC006",
+            Rule::IncorrectStrategyType => "PTC007",
+            Rule::IncorrectMockArgumentType => "PTC008",
+            Rule::DuplicateFixture => "PTC009",
+            Rule::IncompatibleFixtureOverride => "PTC010",
+            Rule::UnusedFixture => "PTC011",
+            Rule::UnsupportedAnnotation => "PTC012",
+            Rule::UnparsableFile => "PTC013",
+            Rule::UnreadableFile => "PTC014",
+            Rule::UnusedSuppression => "PTC015",
+            Rule::IncorrectInferredArgumentType => "PTC016",
+        }
+    }
+
+    /// The name shown in the diagnostic's tag, e.g. `MISSING_ARGUMENT_TYPE`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::FixtureMissingReturnType => "FIXTURE_MISSING_RETURN_TYPE",
+            Rule::IncorrectArgumentType => "INCORRECT_ARGUMENT_TYPE",
+            Rule::MissingArgumentType => "MISSING_ARGUMENT_TYPE",
+            Rule::IncorrectInferredArgumentType => "INCORRECT_INFERRED_ARGUMENT_TYPE",
+            Rule::FixtureDoesNotExist => "FIXTURE_DOES_NOT_EXIST",
+            Rule::IncorrectFixtureValueType => "INCORRECT_FIXTURE_VALUE_TYPE",
+            Rule::FixtureCalledDirectly => "FIXTURE_CALLED_DIRECTLY",
+            Rule::IncorrectStrategyType => "INCORRECT_STRATEGY_TYPE",
+            Rule::IncorrectMockArgumentType => "INCORRECT_MOCK_ARGUMENT_TYPE",
+            Rule::DuplicateFixture => "DUPLICATE_FIXTURE",
+            Rule::IncompatibleFixtureOverride => "INCOMPATIBLE_FIXTURE_OVERRIDE",
+            Rule::UnusedFixture => "UNUSED_FIXTURE",
+            Rule::UnsupportedAnnotation => "UNSUPPORTED_ANNOTATION",
+            Rule::UnparsableFile => "UNPARSABLE_FILE",
+            Rule::UnreadableFile => "UNREADABLE_FILE",
+            Rule::UnusedSuppression => "UNUSED_SUPPRESSION",
+        }
+    }
+
+    /// Whether the rule is active without being selected. Rules that need a project-wide view or
+    /// enforce a stricter style are opt-in.
+    pub fn is_default(self) -> bool {
+        !matches!(
+            self,
+            Rule::IncorrectMockArgumentType | Rule::UnusedFixture | Rule::UnusedSuppression
+        )
+    }
+
+    /// Whether a selector, which is `ALL`, a code, a code prefix of at least `PTC`, or a rule's
+    /// name, selects the rule.
+    pub fn matches(self, selector: &str) -> bool {
+        selector == "ALL"
+            || (selector.starts_with("PTC") && self.code().starts_with(selector))
+            || self.name().eq_ignore_ascii_case(selector)
+    }
+}
+
+impl AnalysisError {
+    pub fn rule(&self) -> Rule {
+        match self {
+            AnalysisError::FixtureMissingReturnType { .. } => Rule::FixtureMissingReturnType,
+            AnalysisError::IncorrectArgumentType { .. } => Rule::IncorrectArgumentType,
+            AnalysisError::MissingArgumentType { .. } => Rule::MissingArgumentType,
+            AnalysisError::IncorrectInferredArgumentType { .. } => {
+                Rule::IncorrectInferredArgumentType
+            }
+            AnalysisError::FixtureDoesNotExist { .. } => Rule::FixtureDoesNotExist,
+            AnalysisError::IncorrectFixtureValueType { .. } => Rule::IncorrectFixtureValueType,
+            AnalysisError::FixtureCalledDirectly { .. } => Rule::FixtureCalledDirectly,
+            AnalysisError::IncorrectStrategyType { .. } => Rule::IncorrectStrategyType,
+            AnalysisError::IncorrectMockArgumentType { .. } => Rule::IncorrectMockArgumentType,
+            AnalysisError::DuplicateFixture { .. } => Rule::DuplicateFixture,
+            AnalysisError::IncompatibleFixtureOverride { .. } => Rule::IncompatibleFixtureOverride,
+            AnalysisError::UnusedFixture { .. } => Rule::UnusedFixture,
+            AnalysisError::UnsupportedAnnotation { .. } => Rule::UnsupportedAnnotation,
+            AnalysisError::UnparsableFile { .. } => Rule::UnparsableFile,
+            AnalysisError::UnreadableFile { .. } => Rule::UnreadableFile,
+            AnalysisError::UnusedSuppression { .. } => Rule::UnusedSuppression,
+        }
+    }
+}
+
+/// Which rules are reported, following the `select`, `extend-select`, `ignore` and
+/// `per-file-ignores` options.
+#[derive(Debug, Default, Clone)]
+pub struct RuleSelection {
+    /// Replaces the default rules when set.
+    pub select: Option<Vec<String>>,
+    /// Rules enabled on top of the selected ones.
+    pub extend_select: Vec<String>,
+    /// Rules that are never reported, even when selected.
+    pub ignore: Vec<String>,
+    /// Rules ignored in the files matching a glob pattern.
+    pub per_file_ignores: Vec<(String, Vec<String>)>,
+}
+
+/// Trims the selectors given on the command line and drops empty ones, e.g. after a trailing comma.
+pub fn clean_selectors(selectors: Vec<String>) -> Vec<String> {
+    selectors
+        .into_iter()
+        .map(|selector| selector.trim().to_owned())
+        .filter(|selector| !selector.is_empty())
+        .collect()
+}
+
+/// Returns the selectors that do not refer to any rule.
+pub fn get_unknown_selectors<'a, I>(selectors: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    selectors
+        .into_iter()
+        .filter(|selector| !Rule::ALL.iter().any(|rule| rule.matches(selector)))
+        .map(|selector| selector.as_str())
+        .collect()
+}
+
+impl RuleSelection {
+    pub fn is_active(&self, rule: Rule) -> bool {
+        let selected = match &self.select {
+            Some(select) => select.iter().any(|s| rule.matches(s)),
+            None => rule.is_default(),
+        };
+
+        (selected || self.extend_select.iter().any(|s| rule.matches(s)))
+            && !self.ignore.iter().any(|s| rule.matches(s))
+    }
+
+    /// Whether the rule is ignored by a `per-file-ignores` pattern matching the file.
+    pub fn is_ignored_in(&self, rule: Rule, filename: &str) -> bool {
+        let path = Path::new(filename);
+        let path = path.strip_prefix(".").unwrap_or(path);
+
+        self.per_file_ignores.iter().any(|(pattern, selectors)| {
+            Pattern::new(pattern).is_ok_and(|p| p.matches_path(path))
+                && selectors.iter().any(|s| rule.matches(s))
+        })
+    }
+
+    pub fn selectors(&self) -> impl Iterator<Item = &String> {
+        self.select
+            .iter()
+            .flatten()
+            .chain(self.extend_select.iter())
+            .chain(self.ignore.iter())
+            .chain(self.per_file_ignores.iter().flat_map(|(_, s)| s.iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clean_selectors, get_unknown_selectors, Rule, RuleSelection};
+
+    fn selectors(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn assert_rule_codes_are_unique() {
+        for (i, rule) in Rule::ALL.iter().enumerate() {
+            assert!(Rule::ALL[i + 1..].iter().all(|r| r.code() != rule.code()));
+        }
+    }
+
+    #[test]
+    fn assert_rule_selection() {
+        let default = RuleSelection::default();
+        assert!(default.is_active(Rule::MissingArgumentType));
+        assert!(!default.is_active(Rule::UnusedFixture));
+
+        let selection = RuleSelection {
+            select: Some(selectors(&["PTC00"])),
+            extend_select: selectors(&["unused_fixture"]),
+            ignore: selectors(&["PTC003"]),
+            per_file_ignores: vec![("tests/legacy/*.py".to_owned(), selectors(&["PTC002"]))],
+        };
+        assert!(selection.is_active(Rule::FixtureMissingReturnType));
+        assert!(selection.is_active(Rule::UnusedFixture));
+        assert!(!selection.is_active(Rule::MissingArgumentType));
+        assert!(!selection.is_active(Rule::IncompatibleFixtureOverride));
+
+        assert!(selection.is_ignored_in(Rule::IncorrectArgumentType, "./tests/legacy/test_a.py"));
+        assert!(!selection.is_ignored_in(Rule::IncorrectArgumentType, "tests/test_a.py"));
+        assert!(!selection.is_ignored_in(Rule::FixtureDoesNotExist, "tests/legacy/test_a.py"));
+    }
+
+    #[test]
+    fn assert_get_unknown_selectors() {
+        let values = selectors(&[
+            "PTC0",
+            "ALL",
+            "missing_argument_type",
+            "PTC9",
+            "E501",
+            "",
+            "P",
+        ]);
+        assert_eq!(
+            get_unknown_selectors(&values),
+            vec!["PTC9", "E501", "", "P"]
+        );
+    }
+
+    #[test]
+    fn assert_clean_selectors() {
+        assert_eq!(
+            clean_selectors(selectors(&["PTC004", "", " PTC005 "])),
+            vec!["PTC004", "PTC005"]
+        );
+        assert!(!Rule::MissingArgumentType.matches(""));
+        assert!(!Rule::MissingArgumentType.matches("PT"));
+        assert!(Rule::MissingArgumentType.matches("PTC"));
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use crate::{analysis_error::AnalysisError, rules::Rule, rules::RuleSelection};
+
+#[derive(Debug, Default, Clone)]
+pub struct Settings {
+    /// Report fixtures that are never requested anywhere in the checked files.
+    pub unused_fixtures: bool,
+    /// Fixtures that are never reported as unused.
+    pub unused_fixtures_allowlist: Vec<String>,
+    /// Require arguments receiving a `mock.patch` mock to be annotated with a mock class.
+    pub check_mock_annotations: bool,
+    /// Directories absolute module names from `pytest_plugins` and imports are resolved against.
+    /// The current directory is used when empty.
+    pub source_roots: Vec<PathBuf>,
+    /// Which rules are reported.
+    pub rules: RuleSelection,
+    /// Check the arguments receiving fixtures without a return annotation against the type
+    /// inferred from the fixture's body.
+    pub infer_types: bool,
+}
+
+impl Settings {
+    /// Whether a rule is active, either through the rule selection or, for the opt-in rules, their
+    /// dedicated flags.
+    pub fn is_rule_active(&self, rule: Rule) -> bool {
+        let enabled_by_flag = match rule {
+            Rule::UnusedFixture => self.unused_fixtures,
+            Rule::IncorrectMockArgumentType => self.check_mock_annotations,
+            _ => false,
+        };
+
+        if enabled_by_flag {
+            !self.rules.ignore.iter().any(|s| rule.matches(s))
+        } else {
+            self.rules.is_active(rule)
+        }
+    }
+
+    /// Whether a diagnostic found in `filename` should be reported.
+    pub fn reports(&self, err: &AnalysisError, filename: &str) -> bool {
+        self.is_rule_active(err.rule()) && !self.rules.is_ignored_in(err.rule(), filename)
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{builtins::BUILTIN_FIXTURES, fixture_index::FixtureDefinition};
+
+/// The maximum number of suggestions given for an unknown fixture.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// A known fixture whose name is close to an unknown one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct FixtureSuggestion {
+    pub name: String,
+    /// Where the fixture is defined, `None` for fixtures built into pytest.
+    pub location: Option<String>,
+}
+
+impl std::fmt::Display for FixtureSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{} ({})", self.name, location),
+            None => write!(f, "{} (builtin)", self.name),
+        }
+    }
+}
+
+/// Returns the fixtures, among the visible and the builtin ones, whose names are within a few edits
+/// of `name`, closest first.
+pub fn suggest_fixtures(
+    name: &str,
+    fixtures: &HashMap<String, FixtureDefinition>,
+) -> Vec<FixtureSuggestion> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut candidates: Vec<(usize, FixtureSuggestion)> = fixtures
+        .iter()
+        .map(|(candidate, definition)| (candidate.as_str(), Some(definition.location())))
+        .chain(
+            BUILTIN_FIXTURES
+                .iter()
+                .filter(|builtin| !fixtures.contains_key(**builtin))
+                .map(|builtin| (*builtin, None)),
+        )
+        .filter(|(candidate, _)| *candidate != name)
+        .map(|(candidate, location)| {
+            (
+                strsim::damerau_levenshtein(name, candidate),
+                FixtureSuggestion {
+                    name: candidate.to_owned(),
+                    location,
+                },
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    candidates.sort_by(|(a, x), (b, y)| a.cmp(b).then_with(|| x.name.cmp(&y.name)));
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, suggestion)| suggestion)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{suggest_fixtures, FixtureSuggestion};
+
+    #[test]
+    fn assert_suggest_fixtures() {
+        let fixtures = HashMap::new();
+        let names = |name: &str| -> Vec<String> {
+            suggest_fixtures(name, &fixtures)
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+
+        assert_eq!(names("tmp_pth"), vec!["tmp_path"]);
+        assert_eq!(names("capsy"), vec!["capsys"]);
+        assert_eq!(names("database_session"), Vec::<String>::new());
+        assert_eq!(
+            suggest_fixtures("monkeypath", &fixtures),
+            vec![FixtureSuggestion {
+                name: "monkeypatch".to_owned(),
+                location: None
+            }]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use rustpython_parser::{lexer::lex, Tok};
+use rustpython_parser_core::Mode;
+use rustpython_parser_vendored::text_size::TextRange;
+
+use crate::{
+    analysis_error::AnalysisError,
+    files::{parsed_python_file::ParsedPythonFile, python_file::PythonFile},
+    location::{function_line, primary_span},
+};
+
+const DIRECTIVE: &str = "pytest-typechecker:";
+
+/// An `ignore` comment, silencing the diagnostics reported on its line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suppression {
+    pub line: usize,
+    /// The range of the comment.
+    pub range: TextRange,
+    /// The selected rules, every rule when empty.
+    pub codes: Vec<String>,
+    /// Whether the comment silenced at least one diagnostic.
+    pub used: bool,
+}
+
+/// The suppression comments of a file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Suppressions {
+    /// Set by a `# pytest-typechecker: skip-file` comment.
+    pub skip_file: bool,
+    pub comments: Vec<Suppression>,
+}
+
+enum Directive {
+    SkipFile,
+    Ignore(Vec<String>),
+}
+
+/// Parses the directive of a comment such as `# noqa  # pytest-typechecker: ignore[PTC003]`.
+fn parse_directive(comment: &str) -> Option<Directive> {
+    let position = comment.find(DIRECTIVE)?;
+    let directive = comment[position + DIRECTIVE.len()..].trim_start();
+
+    if directive.starts_with("skip-file") {
+        return Some(Directive::SkipFile);
+    }
+    let rest = directive.strip_prefix("ignore")?;
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        return Some(Directive::Ignore(Vec::new()));
+    }
+
+    let codes = rest.strip_prefix('[')?.split_once(']')?.0;
+    Some(Directive::Ignore(
+        codes
+            .split(',')
+            .map(|code| code.trim().to_owned())
+            .filter(|code| !code.is_empty())
+            .collect(),
+    ))
+}
+
+impl Suppressions {
+    /// Collects the suppression comments of a file. Only actual comments are considered, so a
+    /// directive inside a string does not suppress anything, and lexing stops at the first error.
+    pub fn new(file: &PythonFile) -> Self {
+        let mut suppressions = Suppressions::default();
+
+        for (token, range) in lex(&file.content, Mode::Module).map_while(Result::ok) {
+            let Tok::Comment(comment) = token else {
+                continue;
+            };
+
+            match parse_directive(&comment) {
+                Some(Directive::SkipFile) => suppressions.skip_file = true,
+                Some(Directive::Ignore(codes)) => suppressions.comments.push(Suppression {
+                    line: file.line_number(range.start()),
+                    range,
+                    codes,
+                    used: false,
+                }),
+                None => {}
+            }
+        }
+
+        suppressions
+    }
+
+    /// Whether the diagnostic is suppressed by a comment on one of `lines`, marking the matching
+    /// comments as used.
+    fn suppresses(&mut self, err: &AnalysisError, lines: &[usize]) -> bool {
+        let mut suppressed = false;
+
+        for suppression in self.comments.iter_mut() {
+            let matches_rule = suppression.codes.is_empty()
+                || suppression
+                    .codes
+                    .iter()
+                    .any(|code| err.rule().matches(code));
+            if lines.contains(&suppression.line) && matches_rule {
+                suppression.used = true;
+                suppressed = true;
+            }
+        }
+
+        suppressed
+    }
+}
+
+/// Removes the diagnostics of a file that are silenced by its suppression comments, either on the
+/// line the diagnostic points at or on the `def` line of the function it is about.
+pub fn apply_suppressions(file: &mut ParsedPythonFile) {
+    let mut errors = std::mem::take(&mut file.errors);
+    let mut suppressions = std::mem::take(&mut file.suppressions);
+
+    if suppressions.skip_file {
+        errors.clear();
+    }
+    errors.retain(|err| {
+        let lines: Vec<usize> = primary_span(err, file)
+            .map(|span| span.line)
+            .into_iter()
+            .chain(function_line(err, file))
+            .collect();
+        !suppressions.suppresses(err, &lines)
+    });
+
+    file.errors = errors;
+    file.suppressions = suppressions;
+}
+
+/// Reports the suppression comments of a file that silence no diagnostic.
+pub fn check_unused_suppressions(file: &ParsedPythonFile) -> Vec<AnalysisError> {
+    file.suppressions
+        .comments
+        .iter()
+        .filter(|suppression| !suppression.used)
+        .map(|suppression| AnalysisError::UnusedSuppression {
+            line: suppression.line,
+            codes: suppression.codes.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_parser_vendored::text_size::TextRange;
+
+    use crate::files::python_file::PythonFile;
+
+    use super::{Suppression, Suppressions};
+
+    #[test]
+    fn assert_collect_suppressions() {
+        let file = PythonFile {
+            content: "x = '# pytest-typechecker: ignore'\ny = 1  # pytest-typechecker: ignore\n# noqa # pytest-typechecker: ignore[PTC003, PTC002]\n# pytest-typechecker: ignored\n".to_owned(),
+            filename: "test_file.py".to_owned(),
+        };
+
+        assert_eq!(
+            Suppressions::new(&file),
+            Suppressions {
+                skip_file: false,
+                comments: vec![
+                    Suppression {
+                        line: 2,
+                        range: TextRange::new(42.into(), 70.into()),
+                        codes: vec![],
+                        used: false
+                    },
+                    Suppression {
+                        line: 3,
+                        range: TextRange::new(71.into(), 122.into()),
+                        codes: vec!["PTC003".to_owned(), "PTC002".to_owned()],
+                        used: false
+                    },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn assert_skip_file() {
+        let file = PythonFile {
+            content: "import pytest\n\n# pytest-typechecker: skip-file\n".to_owned(),
+            filename: "test_file.py".to_owned(),
+        };
+
+        assert!(Suppressions::new(&file).skip_file);
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+
+use rustpython_ast::{
+    Arguments, Comprehension, ExceptHandlerExceptHandler, Expr, ExprAttribute, ExprCall,
+    ExprContext, ExprDictComp, ExprGeneratorExp, ExprLambda, ExprListComp, ExprName, ExprSetComp,
+    Ranged, Stmt, StmtAnnAssign, StmtAsyncFunctionDef, StmtClassDef, StmtFunctionDef, StmtImport,
+    StmtImportFrom, Visitor,
+};
+use rustpython_parser_vendored::text_size::TextRange;
+
+use crate::{
+    files::parsed_python_file::ParsedPythonFile,
+    functions::get_fixture_arguments,
+    nodes::{get_pytestmark, get_string_literal, get_usefixtures},
+};
+
+/// A `request.getfixturevalue("name")` call with a literal fixture name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureValueLookup {
+    pub fixture_name: String,
+    /// The range of the call.
+    pub range: TextRange,
+    /// Set when the result is assigned to an annotated variable, e.g. `x: Foo = ...`.
+    pub target: Option<(String, Expr)>,
+}
+
+#[derive(Default)]
+struct FixtureValueVisitor {
+    lookups: Vec<FixtureValueLookup>,
+}
+
+impl Visitor for FixtureValueVisitor {
+    fn visit_stmt_ann_assign(&mut self, node: StmtAnnAssign) {
+        let lookup = match (node.value.as_deref(), node.target.as_name_expr()) {
+            (Some(Expr::Call(call)), Some(target)) => {
+                get_fixture_value_name(call).map(|name| (name, call.range(), target.id.to_string()))
+            }
+            _ => None,
+        };
+
+        match lookup {
+            Some((fixture_name, range, variable_name)) => self.lookups.push(FixtureValueLookup {
+                fixture_name,
+                range,
+                target: Some((variable_name, *node.annotation)),
+            }),
+            None => self.generic_visit_stmt_ann_assign(node),
+        }
+    }
+
+    fn visit_expr_call(&mut self, node: ExprCall) {
+        if let Some(fixture_name) = get_fixture_value_name(&node) {
+            self.lookups.push(FixtureValueLookup {
+                fixture_name,
+                range: node.range(),
+                target: None,
+            });
+        }
+        self.generic_visit_expr_call(node)
+    }
+}
+
+/// Returns the names of the parameters of a function or lambda.
+pub fn get_argument_names(args: &Arguments) -> HashSet<String> {
+    args.posonlyargs
+        .iter()
+        .chain(args.args.iter())
+        .chain(args.kwonlyargs.iter())
+        .map(|arg| &arg.def)
+        .chain(args.vararg.as_deref())
+        .chain(args.kwarg.as_deref())
+        .map(|arg| arg.arg.to_string())
+        .collect()
+}
+
+/// Returns the names bound by the targets of a comprehension's `for` clauses.
+fn get_comprehension_names(generators: &[Comprehension]) -> HashSet<String> {
+    let mut visitor = BoundNamesVisitor::default();
+    for generator in generators {
+        visitor.visit_expr(generator.target.clone());
+    }
+    visitor.names
+}
+
+/// Collects the calls of a function body, leaving out the calls made in a nested function, lambda
+/// or comprehension to a name that this nested scope binds.
+#[derive(Default)]
+struct CalledNamesVisitor {
+    names: Vec<ExprName>,
+    /// The names bound by each nested scope the visitor is in.
+    scopes: Vec<HashSet<String>>,
+}
+
+impl CalledNamesVisitor {
+    fn is_bound(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+    }
+
+    /// Visits the defaults of a function's or lambda's parameters, which belong to the enclosing
+    /// scope.
+    fn visit_defaults(&mut self, args: &Arguments) {
+        let defaults = args
+            .posonlyargs
+            .iter()
+            .chain(args.args.iter())
+            .chain(args.kwonlyargs.iter())
+            .filter_map(|arg| arg.default.clone());
+        for default in defaults {
+            self.visit_expr(*default);
+        }
+    }
+
+    fn visit_function(&mut self, decorators: Vec<Expr>, args: &Arguments, body: Vec<Stmt>) {
+        for decorator in decorators {
+            self.visit_expr(decorator);
+        }
+        self.visit_defaults(args);
+
+        let mut bound = get_bound_names(&body);
+        bound.extend(get_argument_names(args));
+        self.scopes.push(bound);
+        for stmt in body {
+            self.visit_stmt(stmt);
+        }
+        self.scopes.pop();
+    }
+
+    /// Visits a comprehension, whose first iterable is evaluated in the enclosing scope.
+    fn visit_comprehension_scope(
+        &mut self,
+        generators: Vec<Comprehension>,
+        elements: impl IntoIterator<Item = Expr>,
+    ) {
+        let bound = get_comprehension_names(&generators);
+        let mut generators = generators.into_iter();
+        let Some(first) = generators.next() else {
+            return;
+        };
+        self.visit_expr(first.iter);
+
+        self.scopes.push(bound);
+        for element in elements {
+            self.visit_expr(element);
+        }
+        for condition in first.ifs {
+            self.visit_expr(condition);
+        }
+        for generator in generators {
+            self.visit_expr(generator.iter);
+            for condition in generator.ifs {
+                self.visit_expr(condition);
+            }
+        }
+        self.scopes.pop();
+    }
+}
+
+impl Visitor for CalledNamesVisitor {
+    fn visit_expr_call(&mut self, node: ExprCall) {
+        if let Expr::Name(name) = &*node.func {
+            if !self.is_bound(name.id.as_str()) {
+                self.names.push(name.clone());
+            }
+        }
+        self.generic_visit_expr_call(node)
+    }
+
+    fn visit_stmt_function_def(&mut self, node: StmtFunctionDef) {
+        self.visit_function(node.decorator_list, &node.args, node.body);
+    }
+
+    fn visit_stmt_async_function_def(&mut self, node: StmtAsyncFunctionDef) {
+        self.visit_function(node.decorator_list, &node.args, node.body);
+    }
+
+    fn visit_expr_lambda(&mut self, node: ExprLambda) {
+        self.visit_defaults(&node.args);
+
+        self.scopes.push(get_argument_names(&node.args));
+        self.visit_expr(*node.body);
+        self.scopes.pop();
+    }
+
+    fn visit_expr_list_comp(&mut self, node: ExprListComp) {
+        self.visit_comprehension_scope(node.generators, [*node.elt]);
+    }
+
+    fn visit_expr_set_comp(&mut self, node: ExprSetComp) {
+        self.visit_comprehension_scope(node.generators, [*node.elt]);
+    }
+
+    fn visit_expr_dict_comp(&mut self, node: ExprDictComp) {
+        self.visit_comprehension_scope(node.generators, [*node.key, *node.value]);
+    }
+
+    fn visit_expr_generator_exp(&mut self, node: ExprGeneratorExp) {
+        self.visit_comprehension_scope(node.generators, [*node.elt]);
+    }
+}
+
+/// Collects the called names of the plain `name(...)` calls in `stmts`, including those of nested
+/// scopes unless the nested scope binds the called name.
+pub fn get_called_name_nodes(stmts: &[Stmt]) -> Vec<ExprName> {
+    let mut visitor = CalledNamesVisitor::default();
+    for stmt in stmts {
+        visitor.visit_stmt(stmt.clone());
+    }
+    visitor.names
+}
+
+/// Collects the names of the plain `name(...)` calls in `stmts`, scoped as by
+/// `get_called_name_nodes`.
+pub fn get_called_names(stmts: &[Stmt]) -> Vec<String> {
+    get_called_name_nodes(stmts)
+        .into_iter()
+        .map(|name| name.id.to_string())
+        .collect()
+}
+
+#[derive(Default)]
+struct BoundNamesVisitor {
+    names: HashSet<String>,
+}
+
+impl Visitor for BoundNamesVisitor {
+    fn visit_expr_name(&mut self, node: ExprName) {
+        if node.ctx == ExprContext::Store {
+            self.names.insert(node.id.to_string());
+        }
+    }
+
+    fn visit_stmt_import(&mut self, node: StmtImport) {
+        for alias in node.names {
+            let name = match &alias.asname {
+                Some(asname) => asname.to_string(),
+                None => alias.name.split('.').next().unwrap_or_default().to_owned(),
+            };
+            self.names.insert(name);
+        }
+    }
+
+    fn visit_stmt_import_from(&mut self, node: StmtImportFrom) {
+        for alias in node.names {
+            self.names
+                .insert(alias.asname.unwrap_or(alias.name).to_string());
+        }
+    }
+
+    // The bodies of nested definitions bind names in their own scope.
+    fn visit_stmt_function_def(&mut self, node: StmtFunctionDef) {
+        self.names.insert(node.name.to_string());
+    }
+
+    fn visit_stmt_async_function_def(&mut self, node: StmtAsyncFunctionDef) {
+        self.names.insert(node.name.to_string());
+    }
+
+    fn visit_stmt_class_def(&mut self, node: StmtClassDef) {
+        self.names.insert(node.name.to_string());
+    }
+
+    // Lambdas and comprehensions bind their parameters and targets in their own scope.
+    fn visit_expr_lambda(&mut self, _node: ExprLambda) {}
+
+    fn visit_expr_list_comp(&mut self, _node: ExprListComp) {}
+
+    fn visit_expr_set_comp(&mut self, _node: ExprSetComp) {}
+
+    fn visit_expr_dict_comp(&mut self, _node: ExprDictComp) {}
+
+    fn visit_expr_generator_exp(&mut self, _node: ExprGeneratorExp) {}
+
+    fn visit_excepthandler_except_handler(&mut self, node: ExceptHandlerExceptHandler) {
+        if let Some(name) = &node.name {
+            self.names.insert(name.to_string());
+        }
+        self.generic_visit_excepthandler_except_handler(node)
+    }
+}
+
+/// Collects the names bound in the scope of a function body: assignment, `for`, `with` and
+/// `except` targets, imports and nested definitions.
+pub fn get_bound_names(stmts: &[Stmt]) -> HashSet<String> {
+    let mut visitor = BoundNamesVisitor::default();
+    for stmt in stmts {
+        visitor.visit_stmt(stmt.clone());
+    }
+    visitor.names
+}
+
+/// Returns the fixture name of a `request.getfixturevalue("name")` call.
+pub fn get_fixture_value_name(call: &ExprCall) -> Option<String> {
+    match &*call.func {
+        Expr::Attribute(ExprAttribute { attr, .. }) if attr == "getfixturevalue" => {
+            get_string_literal(call.args.first()?)
+        }
+        _ => None,
+    }
+}
+
+/// Collects the `request.getfixturevalue` calls with a literal argument anywhere in `stmts`.
+pub fn get_fixture_value_lookups(stmts: &[Stmt]) -> Vec<FixtureValueLookup> {
+    let mut visitor = FixtureValueVisitor::default();
+    for stmt in stmts {
+        visitor.visit_stmt(stmt.clone());
+    }
+    visitor.lookups
+}
+
+/// Collects every fixture name that a file requests, either as a function argument, through a
+/// `usefixtures` mark or through `request.getfixturevalue`.
+pub fn get_requested_fixtures(file: &ParsedPythonFile) -> HashSet<String> {
+    let mut requested = HashSet::new();
+
+    for func in file.fixtures.values().chain(file.test_cases.values()) {
+        requested.extend(
+            get_fixture_arguments(func)
+                .iter()
+                .map(|arg| arg.def.arg.to_string()),
+        );
+        requested.extend(func.decorator_list.iter().flat_map(get_usefixtures));
+    }
+
+    for stmt in &file.body {
+        if let Stmt::ClassDef(class) = stmt {
+            requested.extend(class.decorator_list.iter().flat_map(get_usefixtures));
+        }
+    }
+
+    if let Some(pytestmark) = get_pytestmark(&file.body) {
+        requested.extend(get_usefixtures(pytestmark));
+    }
+
+    requested.extend(
+        get_fixture_value_lookups(&file.body)
+            .into_iter()
+            .map(|lookup| lookup.fixture_name),
+    );
+
+    requested
+}
+
+#[cfg(test)]
+mod tests {
+    use rustpython_ast::{Expr, Suite};
+    use rustpython_parser::Parse;
+
+    use super::{get_called_names, get_fixture_value_lookups};
+
+    #[test]
+    fn assert_get_fixture_value_lookups() {
+        let stmts = Suite::parse(
+            "x: int = request.getfixturevalue('a')\nprint(request.getfixturevalue('b'))\n",
+            "<test>",
+        )
+        .unwrap();
+
+        let lookups = get_fixture_value_lookups(&stmts);
+        let names: Vec<&str> = lookups.iter().map(|l| l.fixture_name.as_str()).collect();
+
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(matches!(
+            &lookups[0].target,
+            Some((name, Expr::Name(annotation))) if name == "x" && annotation.id.as_str() == "int"
+        ));
+        assert_eq!(lookups[1].target, None);
+    }
+
+    #[test]
+    fn assert_get_called_names() {
+        let stmts = Suite::parse("user = make_user(factory.build(), other())\n", "<test>").unwrap();
+
+        assert_eq!(get_called_names(&stmts), vec!["make_user", "other"]);
+    }
+
+    #[test]
+    fn assert_get_called_names_of_nested_scopes() {
+        let stmts = Suite::parse(
+            "def helper():\n    admin = str\n    return admin()\ncall = lambda admin: admin()\nnames = [user() for user in users()]\nother = lambda: make_user()\n",
+            "<test>",
+        )
+        .unwrap();
+
+        assert_eq!(get_called_names(&stmts), vec!["users", "make_user"]);
+    }
+}